@@ -0,0 +1,29 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+/// Options for re-running speech-to-text on every clip.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct RetranscribeRequest {
+    /// Leave clips whose detected speech was corrected by hand alone.
+    #[serde(default)]
+    pub skip_edited: bool,
+}
+
+/// The progress of re-running speech-to-text on every clip.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct RetranscribeStatus {
+    /// Whether the job is still running.
+    pub running: bool,
+    /// When the most recent job started.
+    pub started_on: Option<NaiveDateTime>,
+    /// When the most recent job finished.
+    pub finished_on: Option<NaiveDateTime>,
+    /// The number of clips the job will process.
+    pub total: u64,
+    /// The number of clips that have been re-transcribed.
+    pub transcribed: u64,
+    /// The number of clips that were left alone because their speech was edited by hand.
+    pub skipped: u64,
+    /// The number of clips that could not be re-transcribed.
+    pub failed: u64,
+}
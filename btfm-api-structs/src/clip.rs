@@ -1,4 +1,4 @@
-use chrono::NaiveDateTime;
+use chrono::{NaiveDateTime, Weekday};
 use serde::{Deserialize, Serialize};
 
 use crate::Phrases;
@@ -23,6 +23,29 @@ pub struct Clip {
     pub audio_file: String,
     /// The name of the file when it was uploaded.
     pub original_file_name: String,
+    /// How likely the clip is to be picked relative to other clips; 0 means it is only played
+    /// when triggered by a phrase.
+    pub weight: f64,
+    /// Overrides the server's configured time, in seconds, before the clip can be played again.
+    pub cooldown: Option<i64>,
+    /// Whether `speech_detected` was corrected by hand rather than set by speech-to-text.
+    #[serde(default)]
+    pub speech_detected_edited: bool,
+    /// Whether the clip is triggered by its detected speech, in addition to its phrases.
+    #[serde(default = "default_true")]
+    pub match_speech_detected: bool,
+    /// Disabled clips are never played, but keep their audio and phrases.
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// When the clip was moved to the trash; `None` unless the clip is in the trash.
+    #[serde(default)]
+    pub deleted_on: Option<NaiveDateTime>,
+    /// The names of the clip's tags.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// When the clip can play; if empty, it can always play.
+    #[serde(default)]
+    pub availability: Vec<AvailabilityWindow>,
     /// Phrases associated with the clip.
     pub phrases: Option<Phrases>,
 }
@@ -31,13 +54,172 @@ pub struct Clip {
 pub struct Clips {
     pub items: u64,
     pub clips: Vec<Clip>,
+    /// Pass this as the `cursor` query parameter to get the next page of clips; if it's not
+    /// set, this is the last page.
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+}
+
+/// The fields clips can be sorted by.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClipSort {
+    Plays,
+    #[default]
+    CreatedOn,
+    LastPlayed,
+    Title,
+}
+
+/// The direction to sort in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Query parameters for listing clips.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ClipQuery {
+    /// The maximum number of clips to return.
+    pub limit: Option<u32>,
+    /// The `next_cursor` from the previous page of clips.
+    pub cursor: Option<String>,
+    #[serde(default)]
+    pub sort: ClipSort,
+    #[serde(default)]
+    pub order: SortOrder,
+    /// Only list clips that are enabled or disabled.
+    pub enabled: Option<bool>,
+    /// Only list clips with this tag.
+    pub tag: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ClipUpload {
     pub title: String,
     pub description: String,
+    /// The clip's phrases. On edits, this replaces the clip's phrases, but phrases it already
+    /// has keep their settings; they're left unchanged if not provided.
     pub phrases: Option<Vec<String>>,
+    /// The clip's weight when picking clips at random; defaults to 1 for new clips and is left
+    /// unchanged on edits if not provided.
+    #[serde(default)]
+    pub weight: Option<f64>,
+    /// Overrides the server's configured cooldown for the clip, in seconds, up to a year; left
+    /// unchanged on edits if not provided.
+    #[serde(default)]
+    pub cooldown: Option<i64>,
+    /// Corrects the clip's detected speech; left unchanged on edits if not provided. This is
+    /// ignored when creating clips.
+    #[serde(default)]
+    pub speech_detected: Option<String>,
+    /// Whether the clip is triggered by its detected speech; defaults to true for new clips and
+    /// is left unchanged on edits if not provided.
+    #[serde(default)]
+    pub match_speech_detected: Option<bool>,
+}
+
+/// Changes to a clip's metadata; fields that aren't provided are left unchanged.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ClipPatch {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Phrases to add to the clip; phrases the clip already has are ignored.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub add_phrases: Vec<String>,
+    /// Phrases to remove from the clip, matched by their text; phrases the clip doesn't have are
+    /// ignored. Phrases are removed before any are added.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub remove_phrases: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weight: Option<f64>,
+    /// Set to `null` to remove the clip's cooldown override and use the server's default.
+    #[serde(
+        deserialize_with = "deserialize_some",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub cooldown: Option<Option<i64>>,
+    /// Corrects the clip's detected speech.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub speech_detected: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub match_speech_detected: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    /// Replaces the clip's availability windows; an empty list lets the clip always play.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub availability: Option<Vec<AvailabilityWindow>>,
+}
+
+/// A recurring window of time, in the server's configured timezone, when a clip can play.
+///
+/// A clip can play if any of its windows are open. A window is open when every condition that's
+/// set holds; a window without any conditions is always open. Ranges that end before they start
+/// wrap around, so December 15th to January 5th, or 10 PM to 2 AM, work as expected.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AvailabilityWindow {
+    /// The first day of a yearly date range, as "MM-DD"; `end_date` must also be set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_date: Option<String>,
+    /// The last day of the date range, inclusive, as "MM-DD".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_date: Option<String>,
+    /// The days of the week the window is open; if empty, it's open every day.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub weekdays: Vec<Weekday>,
+    /// The hour, from 0 to 23, the window opens each day; `end_hour` must also be set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_hour: Option<u32>,
+    /// The hour, from 1 to 24, the window closes each day.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_hour: Option<u32>,
+}
+
+/// Distinguish a field set to `null` from a missing field; missing fields use the default of
+/// `None`, while `null` becomes `Some(None)`.
+fn deserialize_some<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: serde::Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Query parameters for searching clips.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ClipSearchQuery {
+    /// The words to search for in clip titles, descriptions, detected speech, and phrases.
+    pub q: String,
+    /// The maximum number of results to return.
+    pub limit: Option<u32>,
+}
+
+/// A clip found by searching.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ClipSearchResult {
+    pub clip: Clip,
+    /// How well the clip matched the search; lower is better.
+    pub rank: f64,
+    /// An excerpt of the matching text, with matching words surrounded by `[` and `]`.
+    pub snippet: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ClipSearchResults {
+    pub items: u64,
+    /// Results, best match first.
+    pub results: Vec<ClipSearchResult>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
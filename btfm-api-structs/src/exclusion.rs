@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+/// A phrase that stops a clip from playing when it appears in the transcript, even if one of the
+/// clip's phrases matched.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Exclusion {
+    pub uuid: String,
+    /// The clip the phrase vetoes.
+    pub clip: String,
+    pub phrase: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CreateExclusion {
+    /// The phrase.
+    pub phrase: String,
+    /// The clip the phrase vetoes.
+    pub clip: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Exclusions {
+    pub items: u64,
+    pub exclusions: Vec<Exclusion>,
+}
@@ -1,11 +1,29 @@
 /// Defines public-facing structures used in the web API
 use serde::{Deserialize, Serialize};
 
+mod admin;
 mod clip;
+mod exclusion;
+mod matching;
 mod phrase;
+mod play;
+mod stats;
+mod tag;
 
-pub use clip::{Clip, ClipUpdated, ClipUpload, Clips};
-pub use phrase::{CreatePhrase, Phrase, Phrases};
+pub use admin::{RetranscribeRequest, RetranscribeStatus};
+pub use clip::{
+    AvailabilityWindow, Clip, ClipPatch, ClipQuery, ClipSearchQuery, ClipSearchResult,
+    ClipSearchResults, ClipSort, ClipUpdated, ClipUpload, Clips, SortOrder,
+};
+pub use exclusion::{CreateExclusion, Exclusion, Exclusions};
+pub use matching::{MatchCandidate, MatchRequest, MatchResult};
+pub use phrase::{
+    ConflictKind, ConflictsQuery, CreatePhrase, Phrase, PhraseConflict, PhraseConflicts,
+    PhraseKind, Phrases,
+};
+pub use play::{HistoryQuery, Play, Plays, Trigger};
+pub use stats::{ClipPlays, DailyPlays, PhrasePlays, Stats, StatsQuery, UnplayedClip};
+pub use tag::{CreateTag, Tag, Tags};
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Status {
@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Clip;
+
+/// Text to match against clips without playing anything.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MatchRequest {
+    /// The transcript, as the bot would hear it.
+    pub text: String,
+    /// The Discord user ID of the speaker, for phrases that only some users can trigger.
+    #[serde(default)]
+    pub speaker: Option<u64>,
+}
+
+/// A clip that would be triggered by the text.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MatchCandidate {
+    pub clip: Clip,
+    /// The phrase, or the clip's detected speech, that matched the text.
+    pub phrase: String,
+    /// How closely the phrase matched, from 0 to 1 where 1 is an exact match.
+    pub score: f64,
+    /// Whether the clip was played too recently to be played again; these clips are skipped.
+    pub cooling_down: bool,
+    /// The exclusion phrase found in the text, if any; these clips are skipped.
+    #[serde(default)]
+    pub excluded_by: Option<String>,
+    /// Whether the clip's tags keep it from playing; these clips are skipped.
+    #[serde(default)]
+    pub muted: bool,
+    /// Whether the clip is outside all of its availability windows; these clips are skipped.
+    #[serde(default)]
+    pub unavailable: bool,
+}
+
+/// The result of matching text against clips.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MatchResult {
+    /// The text after normalization, which is what's matched against phrases.
+    pub normalized_text: String,
+    /// The chance, from 0 to 1, that a clip would be played right now.
+    pub play_chance: f64,
+    /// Whether the text skips rate limiting, in which case a clip would always be played.
+    pub bypasses_rate_limit: bool,
+    /// Every clip that matched the text.
+    pub candidates: Vec<MatchCandidate>,
+}
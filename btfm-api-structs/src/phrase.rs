@@ -1,18 +1,89 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// The kinds of phrases that can trigger a clip.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PhraseKind {
+    /// The phrase is matched against speech after both are normalized: punctuation and
+    /// diacritics are removed, contractions are expanded, and numbers are written as words.
+    #[default]
+    Literal,
+    /// The phrase is a regular expression matched against speech as it was transcribed,
+    /// ignoring case. Speech isn't normalized first, so it may include punctuation and digits.
+    Regex,
+}
 
 /// A phrase used to trigger one or more clips
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Phrase {
     pub uuid: String,
     pub phrase: String,
+    /// The clips the phrase triggers.
+    #[serde(default)]
+    pub clips: Vec<String>,
+    /// The minimum similarity, from 0 to 1, for a fuzzy match of this phrase. If unset, the
+    /// server's default is used.
+    pub similarity_threshold: Option<f64>,
+    /// Match the phrase anywhere in speech, even inside other words, rather than only on
+    /// whole words.
+    #[serde(default)]
+    pub substring: bool,
+    /// Whether the phrase is literal text or a regular expression.
+    #[serde(default)]
+    pub kind: PhraseKind,
+    /// The Discord user IDs allowed to trigger the phrase; if empty, anyone can.
+    #[serde(default)]
+    pub allowed_speakers: Vec<u64>,
+    /// The Discord user IDs that can never trigger the phrase.
+    #[serde(default)]
+    pub denied_speakers: Vec<u64>,
 }
 
+/// A new phrase, or the replacement for an existing phrase.
+///
+/// When creating a phrase identical to an existing one, including its allowed and denied
+/// speakers, the clips are linked to the existing phrase. Otherwise a new phrase is created, so
+/// phrases linked to other clips are never changed.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct CreatePhrase {
     /// The phrase.
     pub phrase: String,
-    /// The clip to associate the phrase to.
-    pub clip: String,
+    /// The clips to associate the phrase to; there must be at least one.
+    ///
+    /// For compatibility with older clients, this is also accepted as `clip` with a single clip.
+    #[serde(alias = "clip", deserialize_with = "one_or_many")]
+    pub clips: Vec<String>,
+    /// The minimum similarity, from 0 to 1, for a fuzzy match of this phrase. If unset, the
+    /// server's default is used.
+    pub similarity_threshold: Option<f64>,
+    /// Match the phrase anywhere in speech, even inside other words, rather than only on
+    /// whole words.
+    #[serde(default)]
+    pub substring: bool,
+    /// Whether the phrase is literal text or a regular expression.
+    #[serde(default)]
+    pub kind: PhraseKind,
+    /// The Discord user IDs allowed to trigger the phrase; if empty, anyone can.
+    #[serde(default)]
+    pub allowed_speakers: Vec<u64>,
+    /// The Discord user IDs that can never trigger the phrase.
+    #[serde(default)]
+    pub denied_speakers: Vec<u64>,
+}
+
+/// Deserialize either a single string or a list of strings.
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(clip) => vec![clip],
+        OneOrMany::Many(clips) => clips,
+    })
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -20,3 +91,48 @@ pub struct Phrases {
     pub items: u64,
     pub phrases: Vec<Phrase>,
 }
+
+/// Query parameters for the phrase conflicts report.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ConflictsQuery {
+    /// Phrases with fewer characters than this, not counting spaces, are reported as too
+    /// short; defaults to 4.
+    pub min_length: Option<usize>,
+}
+
+/// The kinds of problems the phrase conflicts report finds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictKind {
+    /// The phrase has the same text as another phrase.
+    Duplicate,
+    /// The phrase appears within another phrase, so saying the other phrase triggers this
+    /// phrase's clips too.
+    Substring,
+    /// The phrase is so short it's likely to match constantly.
+    TooShort,
+    /// The phrase appears within a clip's detected speech, so saying the phrase can play that
+    /// clip too.
+    SpeechDetected,
+}
+
+/// A phrase that overlaps with other phrases or clips, or is likely to match too often.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PhraseConflict {
+    pub kind: ConflictKind,
+    /// The ID of the phrase with the problem.
+    pub phrase: String,
+    /// The phrase's text.
+    pub text: String,
+    /// The ID of the phrase this one conflicts with or, for [`ConflictKind::SpeechDetected`],
+    /// the ID of the clip.
+    pub other: Option<String>,
+    /// The text of the other phrase, or the clip's detected speech.
+    pub other_text: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PhraseConflicts {
+    pub items: u64,
+    pub conflicts: Vec<PhraseConflict>,
+}
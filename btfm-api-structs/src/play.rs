@@ -0,0 +1,55 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// What caused a clip to be played.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Trigger {
+    /// Someone said a phrase that matched the clip.
+    Phrase,
+    /// The clip was picked by the random clip player.
+    Random,
+    /// The clip was played via the HTTP API.
+    Api,
+}
+
+/// A record of a clip being played.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Play {
+    pub uuid: String,
+    /// The clip that was played.
+    pub clip: String,
+    /// When the clip was played.
+    pub played_on: NaiveDateTime,
+    /// What caused the clip to be played.
+    pub triggered_by: Trigger,
+    /// The Discord user whose speech triggered the clip, if known.
+    pub user_id: Option<u64>,
+    /// The voice stream the triggering speech came from.
+    pub ssrc: Option<u32>,
+    /// What the bot heard, if the clip was triggered by speech.
+    pub transcript: Option<String>,
+    /// The phrase that matched the transcript.
+    pub phrase: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Plays {
+    /// The number of plays in this page of results.
+    pub items: u64,
+    /// The total number of plays matching the query.
+    pub total: u64,
+    pub plays: Vec<Play>,
+}
+
+/// Query parameters for paging through play history.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct HistoryQuery {
+    /// Only show plays of this clip.
+    pub clip: Option<Uuid>,
+    /// The number of plays to return; defaults to 50.
+    pub limit: Option<u32>,
+    /// The number of plays to skip.
+    pub offset: Option<u32>,
+}
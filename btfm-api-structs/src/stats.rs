@@ -0,0 +1,56 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+/// Query parameters for clip statistics.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct StatsQuery {
+    /// The first day to include; defaults to 30 days before `until`.
+    pub since: Option<NaiveDate>,
+    /// The last day to include; defaults to today.
+    pub until: Option<NaiveDate>,
+    /// The maximum number of top clips and phrases to include; defaults to 10.
+    pub limit: Option<u32>,
+}
+
+/// Statistics about clip plays over a range of days.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Stats {
+    /// The first day included in the statistics.
+    pub since: NaiveDate,
+    /// The last day included in the statistics.
+    pub until: NaiveDate,
+    /// The most-played clips, most plays first.
+    pub top_clips: Vec<ClipPlays>,
+    /// The number of plays on each day that had any plays.
+    pub plays_per_day: Vec<DailyPlays>,
+    /// Clips that have never been played, regardless of the time range.
+    pub never_played: Vec<UnplayedClip>,
+    /// The phrases that triggered the most plays, most plays first.
+    pub top_phrases: Vec<PhrasePlays>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ClipPlays {
+    pub uuid: String,
+    pub title: String,
+    pub plays: i64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DailyPlays {
+    pub day: NaiveDate,
+    pub plays: i64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct UnplayedClip {
+    pub uuid: String,
+    pub title: String,
+    pub created_on: NaiveDateTime,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PhrasePlays {
+    pub phrase: String,
+    pub plays: i64,
+}
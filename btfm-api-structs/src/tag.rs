@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+/// A label for a group of clips; the server can be configured to mute clips with some tags, or
+/// to only play clips with certain tags.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Tag {
+    pub uuid: String,
+    /// The tag's unique, lowercase name.
+    pub name: String,
+    /// The clips with the tag.
+    #[serde(default)]
+    pub clips: Vec<String>,
+}
+
+/// The body used to create or rename a tag, and to tag a clip.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CreateTag {
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Tags {
+    pub items: u64,
+    pub tags: Vec<Tag>,
+}
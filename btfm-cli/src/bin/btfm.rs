@@ -1,7 +1,12 @@
 use std::{path::PathBuf, time::Duration};
 
-use btfm_api_structs::{Clip, ClipUpdated, ClipUpload, Clips, CreatePhrase, Phrase, Phrases};
-use chrono::SubsecRound;
+use btfm_api_structs::{
+    AvailabilityWindow, Clip, ClipPatch, ClipQuery, ClipSearchQuery, ClipSearchResults, ClipSort,
+    ClipUpdated, ClipUpload, Clips, ConflictKind, ConflictsQuery, CreateExclusion, CreatePhrase,
+    CreateTag, Exclusion, Exclusions, MatchRequest, MatchResult, Phrase, PhraseConflicts,
+    PhraseKind, Phrases, SortOrder, Stats, StatsQuery, Tag,
+};
+use chrono::{NaiveDate, SubsecRound, Weekday};
 use clap::{Parser, Subcommand};
 use reqwest::{multipart, Body, Url};
 use thiserror::Error as ThisError;
@@ -49,6 +54,27 @@ pub enum Command {
     /// Manage phrases that trigger audio clips
     #[command(subcommand)]
     Phrase(PhraseCommand),
+    /// Show which clips some text would trigger, and why, without playing anything
+    Match {
+        /// The text to match, as the bot would hear it
+        #[arg(required = true)]
+        text: Vec<String>,
+        /// The Discord user ID of the speaker, for phrases restricted to certain speakers
+        #[arg(long)]
+        speaker: Option<u64>,
+    },
+    /// Report which clips are played, and which never are
+    Stats {
+        /// The first day to include (e.g. 2024-01-31); defaults to 30 days before --until
+        #[arg(long)]
+        since: Option<NaiveDate>,
+        /// The last day to include; defaults to today
+        #[arg(long)]
+        until: Option<NaiveDate>,
+        /// The number of top clips and phrases to show
+        #[arg(long, default_value_t = 10)]
+        limit: u32,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -70,18 +96,52 @@ pub enum ClipCommand {
     },
     /// Edit an existing clip in the database.
     ///
-    /// Phrases are replaced rather than appended, so you must provide the complete list of phrases
-    /// each time you edit the clip.
+    /// Only the provided options are changed.
     Edit {
         /// The clip ID (from "clip list")
         #[arg()]
         clip_id: Uuid,
+        /// The clip title; used in the user interface
+        #[arg(short, long)]
+        title: Option<String>,
         /// A short description of the audio clip
         #[arg(short, long)]
         description: Option<String>,
-        /// The phrase or phrases that cause the clip to be played.
+        /// A phrase that causes the clip to be played; may be given multiple times
         #[arg(short, long)]
-        phrases: Option<Vec<String>>,
+        add_phrase: Vec<String>,
+        /// A phrase that should no longer cause the clip to be played; may be given multiple
+        /// times
+        #[arg(short, long)]
+        remove_phrase: Vec<String>,
+        /// How likely the clip is to be picked relative to other clips (default 1); a weight
+        /// of 0 means the clip is only played when triggered by a phrase
+        #[arg(short, long)]
+        weight: Option<f64>,
+        /// The minimum time, in seconds, before the clip can be played again; overrides the
+        /// server's default
+        #[arg(short, long)]
+        cooldown: Option<i64>,
+        /// Remove the clip's cooldown so the server's default is used
+        #[arg(long, conflicts_with = "cooldown")]
+        default_cooldown: bool,
+        /// Correct the speech detected in the clip
+        #[arg(long)]
+        speech: Option<String>,
+        /// Whether the speech detected in the clip should trigger it
+        #[arg(long)]
+        match_speech: Option<bool>,
+        /// Only play the clip during this window, in the server's timezone; may be given
+        /// multiple times, and replaces the clip's existing windows
+        ///
+        /// Windows are any of "dates=MM-DD..MM-DD", "days=DAY,DAY", and "hours=H..H",
+        /// separated by spaces. For example, "dates=12-01..12-31" or "days=sat,sun hours=6..12".
+        /// Hours end before the last hour, and ranges that end before they start wrap around.
+        #[arg(long, value_parser = parse_window)]
+        available: Vec<AvailabilityWindow>,
+        /// Remove the clip's availability windows so it can always play
+        #[arg(long, conflicts_with = "available")]
+        always_available: bool,
     },
     Show {
         /// The clip ID (from "clip list")
@@ -89,18 +149,96 @@ pub enum ClipCommand {
         clip_id: Uuid,
     },
     /// List clips in the database
-    List {},
-    /// Remove clips from the database
+    List {
+        /// The field to sort clips by: plays, created_on, last_played, or title
+        #[arg(long, default_value = "created_on", value_parser = parse_api_enum::<ClipSort>)]
+        sort: ClipSort,
+        /// The order to sort clips in: asc or desc
+        #[arg(long, default_value = "asc", value_parser = parse_api_enum::<SortOrder>)]
+        order: SortOrder,
+        /// Only list clips that are enabled (true) or disabled (false)
+        #[arg(long)]
+        enabled: Option<bool>,
+        /// Only list clips with this tag
+        #[arg(long)]
+        tag: Option<String>,
+    },
+    /// Replace a clip's audio, keeping its ID, plays, and phrases
+    ///
+    /// Speech-to-text is run again on the new audio.
+    ReplaceAudio {
+        /// The clip ID (from "clip list")
+        #[arg()]
+        clip_id: Uuid,
+        /// The filename of the new audio.
+        #[arg()]
+        file: PathBuf,
+    },
+    /// Search clip titles, descriptions, detected speech, and phrases
+    Search {
+        /// The words to search for
+        #[arg(required = true)]
+        query: Vec<String>,
+        /// The maximum number of results to show
+        #[arg(long, default_value_t = 20)]
+        limit: u32,
+    },
+    /// Move a clip to the trash; it can be restored until the trash is purged
     Remove {
         /// The clip ID (from "clip list")
         #[clap()]
         clip_id: Uuid,
     },
+    /// Stop a clip from playing without removing it
+    Disable {
+        /// The clip ID (from "clip list")
+        #[clap()]
+        clip_id: Uuid,
+    },
+    /// Allow a disabled clip to play again
+    Enable {
+        /// The clip ID (from "clip list")
+        #[clap()]
+        clip_id: Uuid,
+    },
+    /// Manage the tags of a clip
+    #[command(subcommand)]
+    Tag(ClipTagCommand),
+    /// List the clips in the trash
+    Trash {},
+    /// Take a clip out of the trash
+    Restore {
+        /// The clip ID (from "clip trash")
+        #[clap()]
+        clip_id: Uuid,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ClipTagCommand {
+    /// Tag a clip; the tag is created if it doesn't exist
+    Add {
+        /// The clip ID (from "clip list")
+        #[clap()]
+        clip_id: Uuid,
+        /// The name of the tag
+        #[clap()]
+        tag: String,
+    },
+    /// Remove a tag from a clip
+    Remove {
+        /// The clip ID (from "clip list")
+        #[clap()]
+        clip_id: Uuid,
+        /// The name of the tag
+        #[clap()]
+        tag: String,
+    },
 }
 
 #[derive(Subcommand, Debug)]
 pub enum PhraseCommand {
-    /// Add a trigger phrase to a clip
+    /// Add a trigger phrase to a clip; if the phrase already exists, the clip shares it
     Add {
         /// The clip ID (from "clip list")
         #[clap()]
@@ -108,33 +246,99 @@ pub enum PhraseCommand {
         /// The phrase to associate with the clip
         #[clap()]
         phrase: String,
+        /// The minimum similarity, from 0 to 1, for a fuzzy match of the phrase; defaults to
+        /// the server's configured threshold
+        #[clap(long)]
+        threshold: Option<f64>,
+        /// Match the phrase anywhere in speech, even in the middle of other words
+        #[clap(long)]
+        substring: bool,
+        /// Treat the phrase as a regular expression, e.g. "(hello|hi) there"
+        #[clap(long, conflicts_with_all = ["substring", "threshold"])]
+        regex: bool,
+        /// Rather than triggering the clip, stop it from playing when the phrase is heard,
+        /// e.g. "not nice" for a clip triggered by "nice"
+        #[clap(long, conflicts_with_all = ["substring", "threshold", "regex"])]
+        exclude: bool,
+        /// Only trigger the clip when this Discord user ID says the phrase; may be given
+        /// multiple times
+        #[clap(long, conflicts_with = "exclude")]
+        allow_speaker: Vec<u64>,
+        /// Never trigger the clip when this Discord user ID says the phrase; may be given
+        /// multiple times
+        #[clap(long, conflicts_with = "exclude")]
+        deny_speaker: Vec<u64>,
+    },
+    /// Edit an existing phrase; only the provided options are changed
+    Edit {
+        /// The phrase ID (from "phrase list")
+        #[clap()]
+        phrase_id: Uuid,
+        /// The new phrase
+        #[clap(short, long)]
+        phrase: Option<String>,
+        /// Link the phrase to this clip (from "clip list"); may be given multiple times
+        #[clap(short, long)]
+        add_clip: Vec<Uuid>,
+        /// Unlink the phrase from this clip; may be given multiple times. A phrase that isn't
+        /// linked to any clips can't be saved; use "phrase remove" instead
+        #[clap(short, long)]
+        remove_clip: Vec<Uuid>,
+        /// The minimum similarity, from 0 to 1, for a fuzzy match of the phrase
+        #[clap(long)]
+        threshold: Option<f64>,
+        /// Use the server's configured similarity threshold for the phrase
+        #[clap(long, conflicts_with = "threshold")]
+        default_threshold: bool,
+        /// Whether to match the phrase anywhere in speech, even in the middle of other words
+        #[clap(long)]
+        substring: Option<bool>,
+        /// Whether the phrase is a regular expression
+        #[clap(long)]
+        regex: Option<bool>,
+        /// Only trigger the clip when this Discord user ID says the phrase; may be given
+        /// multiple times, and replaces the phrase's allowed speakers
+        #[clap(long)]
+        allow_speaker: Vec<u64>,
+        /// Never trigger the clip when this Discord user ID says the phrase; may be given
+        /// multiple times, and replaces the phrase's denied speakers
+        #[clap(long)]
+        deny_speaker: Vec<u64>,
+        /// Let anyone trigger the clip with the phrase
+        #[clap(long, conflicts_with_all = ["allow_speaker", "deny_speaker"])]
+        any_speaker: bool,
     },
-    // /// Remove a phrase as a trigger for a clip
-    // Remove {
-    //     /// The clip ID (from "clip list")
-    //     #[clap()]
-    //     clip_id: Ulid,
-    //     /// The phrase ID (from "phrase list")
-    //     #[clap()]
-    //     phrase_id: Ulid,
-    // },
-    // /// Edit an existing phrase in the database
-    // Edit {
-    //     /// The phrase ID (from "phrase list")
-    //     #[clap()]
-    //     phrase_id: Ulid,
-    //     /// The new phrase
-    //     #[clap(short, long)]
-    //     phrase: String,
-    // },
     /// List phrases in the database
-    List {},
-    // /// Remove phrases from the database
-    // Remove {
-    //     /// The phrase ID (from "phrase list")
-    //     #[clap()]
-    //     phrase_id: Ulid,
-    // },
+    List {
+        /// Only list the phrases for this clip (from "clip list")
+        #[clap(long)]
+        clip: Option<Uuid>,
+        /// List exclusion phrases instead
+        #[clap(long)]
+        exclude: bool,
+    },
+    /// Report phrases that duplicate or overlap other phrases or clips' detected speech, and
+    /// phrases short enough to match constantly
+    Lint {
+        /// Report phrases with fewer characters than this, not counting spaces, as too short
+        #[clap(long, default_value_t = 4)]
+        min_length: usize,
+    },
+    /// Remove a phrase from the database
+    Remove {
+        /// The phrase ID (from "phrase list")
+        #[clap()]
+        phrase_id: Uuid,
+        /// The ID is for an exclusion phrase (from "phrase list --exclude")
+        #[clap(long)]
+        exclude: bool,
+    },
+    /// Show a phrase
+    Show {
+        /// The phrase ID (from "phrase list")
+        #[clap()]
+        phrase_id: Uuid,
+    },
 }
 
 #[tokio::main(flavor = "current_thread")]
@@ -177,6 +381,10 @@ async fn process_command(opts: Cli) -> Result<(), Error> {
                     title,
                     description,
                     phrases,
+                    weight: None,
+                    cooldown: None,
+                    speech_detected: None,
+                    match_speech_detected: None,
                 })?;
                 let clip_metadata_part =
                     multipart::Part::text(clip_metadata).mime_str("application/json")?;
@@ -194,32 +402,118 @@ async fn process_command(opts: Cli) -> Result<(), Error> {
                 println!("{}", serde_json::to_string_pretty(&clip)?);
                 Ok(())
             }
-            ClipCommand::List {} => {
+            ClipCommand::List {
+                sort,
+                order,
+                enabled,
+                tag,
+            } => {
                 let url = opts.url.join("/v1/clips/")?;
+                let mut query = ClipQuery {
+                    sort,
+                    order,
+                    enabled,
+                    tag,
+                    ..Default::default()
+                };
+                let mut clips = vec![];
+                loop {
+                    let response = client
+                        .get(url.clone())
+                        .basic_auth(&opts.user, Some(&opts.password))
+                        .query(&query)
+                        .send()
+                        .await
+                        .map(|resp| resp.error_for_status())??;
+                    let page = response.json::<Clips>().await?;
+                    clips.extend(page.clips);
+                    match page.next_cursor {
+                        Some(cursor) => query.cursor = Some(cursor),
+                        None => break,
+                    }
+                }
+                display_clips(&Clips {
+                    items: clips.len() as u64,
+                    clips,
+                    next_cursor: None,
+                });
+                Ok(())
+            }
+            ClipCommand::ReplaceAudio { clip_id, file } => {
+                let endpoint = format!("/v1/clips/{clip_id}/audio");
+                let url = opts.url.join(&endpoint)?;
+                let file_name = file
+                    .file_name()
+                    .and_then(|f| f.to_str())
+                    .unwrap()
+                    .to_owned();
+                let clip = File::open(file).await?;
+                let clip_len = clip.metadata().await?.len();
+                let clip_stream = FramedRead::new(clip, BytesCodec::new());
+                let clip_part =
+                    multipart::Part::stream_with_length(Body::wrap_stream(clip_stream), clip_len)
+                        .file_name(file_name);
+                let form = multipart::Form::new().part("clip", clip_part);
+                let response = client
+                    .put(url)
+                    .basic_auth(opts.user, Some(opts.password))
+                    .multipart(form)
+                    .send()
+                    .await
+                    .map(|resp| resp.error_for_status())??;
+                let response = response.json::<ClipUpdated>().await?;
+                println!("{}", serde_json::to_string_pretty(&response)?);
+                Ok(())
+            }
+            ClipCommand::Search { query, limit } => {
+                let url = opts.url.join("/v1/clips/search")?;
                 let response = client
                     .get(url)
                     .basic_auth(opts.user, Some(opts.password))
+                    .query(&ClipSearchQuery {
+                        q: query.join(" "),
+                        limit: Some(limit),
+                    })
                     .send()
                     .await
                     .map(|resp| resp.error_for_status())??;
-                let clips = response.json::<Clips>().await?;
-                display_clips(&clips);
+                let results = response.json::<ClipSearchResults>().await?;
+                display_search_results(&results);
                 Ok(())
             }
             ClipCommand::Edit {
                 clip_id,
+                title,
                 description,
-                phrases,
+                add_phrase,
+                remove_phrase,
+                weight,
+                cooldown,
+                default_cooldown,
+                speech,
+                match_speech,
+                available,
+                always_available,
             } => {
                 let endpoint = format!("/v1/clips/{clip_id}");
                 let url = opts.url.join(&endpoint)?;
-                let json = ClipUpload {
-                    title: "".into(),
-                    description: description.unwrap_or_default(),
-                    phrases,
+                let json = ClipPatch {
+                    title,
+                    description,
+                    add_phrases: add_phrase,
+                    remove_phrases: remove_phrase,
+                    weight,
+                    cooldown: match default_cooldown {
+                        true => Some(None),
+                        false => cooldown.map(Some),
+                    },
+                    speech_detected: speech,
+                    match_speech_detected: match_speech,
+                    enabled: None,
+                    availability: (always_available || !available.is_empty()).then_some(available),
                 };
                 let response = client
-                    .put(url)
+                    .patch(url)
                     .basic_auth(opts.user, Some(opts.password))
                     .json(&json)
                     .send()
@@ -230,6 +524,23 @@ async fn process_command(opts: Cli) -> Result<(), Error> {
 
                 Ok(())
             }
+            ClipCommand::Disable { clip_id } | ClipCommand::Enable { clip_id } => {
+                let enabled = matches!(subcommand, ClipCommand::Enable { .. });
+                let url = opts.url.join(&format!("/v1/clips/{clip_id}"))?;
+                let response = client
+                    .patch(url)
+                    .basic_auth(opts.user, Some(opts.password))
+                    .json(&ClipPatch {
+                        enabled: Some(enabled),
+                        ..Default::default()
+                    })
+                    .send()
+                    .await
+                    .map(|resp| resp.error_for_status())??;
+                let response = response.json::<ClipUpdated>().await?;
+                println!("{}", serde_json::to_string_pretty(&response.new_clip)?);
+                Ok(())
+            }
             ClipCommand::Remove { clip_id } => {
                 let endpoint = format!("/v1/clips/{clip_id}");
                 let url = opts.url.join(&endpoint)?;
@@ -244,6 +555,59 @@ async fn process_command(opts: Cli) -> Result<(), Error> {
 
                 Ok(())
             }
+            ClipCommand::Tag(ClipTagCommand::Add { clip_id, tag }) => {
+                let url = opts.url.join(&format!("/v1/clips/{clip_id}/tags/"))?;
+                let response = client
+                    .post(url)
+                    .basic_auth(opts.user, Some(opts.password))
+                    .json(&CreateTag { name: tag })
+                    .send()
+                    .await
+                    .map(|resp| resp.error_for_status())??;
+                let response = response.json::<Tag>().await?;
+                println!("{}", serde_json::to_string_pretty(&response)?);
+                Ok(())
+            }
+            ClipCommand::Tag(ClipTagCommand::Remove { clip_id, tag }) => {
+                let mut url = opts.url.join(&format!("/v1/clips/{clip_id}/tags/"))?;
+                // The tag name is pushed as a segment so it's percent-encoded.
+                url.path_segments_mut()
+                    .expect("HTTP URLs always have a path")
+                    .pop_if_empty()
+                    .push(&tag);
+                let response = client
+                    .delete(url)
+                    .basic_auth(opts.user, Some(opts.password))
+                    .send()
+                    .await
+                    .map(|resp| resp.error_for_status())??;
+                let response = response.json::<Tag>().await?;
+                println!("{}", serde_json::to_string_pretty(&response)?);
+                Ok(())
+            }
+            ClipCommand::Trash {} => {
+                let url = opts.url.join("/v1/trash")?;
+                let response = client
+                    .get(url)
+                    .basic_auth(opts.user, Some(opts.password))
+                    .send()
+                    .await
+                    .map(|resp| resp.error_for_status())??;
+                display_clips(&response.json::<Clips>().await?);
+                Ok(())
+            }
+            ClipCommand::Restore { clip_id } => {
+                let url = opts.url.join(&format!("/v1/trash/{clip_id}/restore"))?;
+                let response = client
+                    .post(url)
+                    .basic_auth(opts.user, Some(opts.password))
+                    .send()
+                    .await
+                    .map(|resp| resp.error_for_status())??;
+                let response = response.json::<Clip>().await?;
+                println!("{}", serde_json::to_string_pretty(&response)?);
+                Ok(())
+            }
             ClipCommand::Show { clip_id } => {
                 let endpoint = format!("/v1/clips/{clip_id}");
                 let url = opts.url.join(&endpoint)?;
@@ -257,32 +621,187 @@ async fn process_command(opts: Cli) -> Result<(), Error> {
                 let clips = Clips {
                     items: 1,
                     clips: vec![response],
+                    next_cursor: None,
                 };
                 display_clips(&clips);
                 Ok(())
             }
         },
+        Command::Match { text, speaker } => {
+            let url = opts.url.join("/v1/match")?;
+            let response = client
+                .post(url)
+                .basic_auth(opts.user, Some(opts.password))
+                .json(&MatchRequest {
+                    text: text.join(" "),
+                    speaker,
+                })
+                .send()
+                .await
+                .map(|resp| resp.error_for_status())??;
+            let result = response.json::<MatchResult>().await?;
+            display_match_result(&result);
+            Ok(())
+        }
+        Command::Stats {
+            since,
+            until,
+            limit,
+        } => {
+            let url = opts.url.join("/v1/stats")?;
+            let response = client
+                .get(url)
+                .basic_auth(opts.user, Some(opts.password))
+                .query(&StatsQuery {
+                    since,
+                    until,
+                    limit: Some(limit),
+                })
+                .send()
+                .await
+                .map(|resp| resp.error_for_status())??;
+            let stats = response.json::<Stats>().await?;
+            display_stats(&stats);
+            Ok(())
+        }
         Command::Phrase(subcommand) => match subcommand {
-            PhraseCommand::List {} => {
-                let url = opts.url.join("/v1/phrases/")?;
+            PhraseCommand::List { clip, exclude } => {
+                let resource = if exclude { "exclusions" } else { "phrases" };
+                let url = match clip {
+                    Some(clip_id) => opts.url.join(&format!("/v1/clips/{clip_id}/{resource}/"))?,
+                    None => opts.url.join(&format!("/v1/{resource}/"))?,
+                };
                 let response = client
                     .get(url)
                     .basic_auth(opts.user, Some(opts.password))
                     .send()
                     .await
                     .map(|resp| resp.error_for_status())??;
-                let phrases = response.json::<Phrases>().await?;
-                println!("{}", serde_json::to_string_pretty(&phrases)?);
+                if exclude {
+                    let exclusions = response.json::<Exclusions>().await?;
+                    println!("{}", serde_json::to_string_pretty(&exclusions)?);
+                } else {
+                    let phrases = response.json::<Phrases>().await?;
+                    println!("{}", serde_json::to_string_pretty(&phrases)?);
+                }
+                Ok(())
+            }
+            PhraseCommand::Add {
+                clip_id,
+                phrase,
+                exclude: true,
+                ..
+            } => {
+                let url = opts.url.join("/v1/exclusions/")?;
+                let response = client
+                    .post(url)
+                    .basic_auth(opts.user, Some(opts.password))
+                    .json(&CreateExclusion {
+                        clip: clip_id.to_string(),
+                        phrase,
+                    })
+                    .send()
+                    .await
+                    .map(|resp| resp.error_for_status())??;
+                let exclusion = response.json::<Exclusion>().await?;
+                println!("{}", serde_json::to_string_pretty(&exclusion)?);
                 Ok(())
             }
-            PhraseCommand::Add { clip_id, phrase } => {
+            PhraseCommand::Add {
+                clip_id,
+                phrase,
+                threshold,
+                substring,
+                regex,
+                exclude: false,
+                allow_speaker,
+                deny_speaker,
+            } => {
                 let url = opts.url.join("/v1/phrases/")?;
                 let response = client
                     .post(url)
                     .basic_auth(opts.user, Some(opts.password))
                     .json(&CreatePhrase {
-                        clip: clip_id.to_string(),
+                        clips: vec![clip_id.to_string()],
                         phrase,
+                        similarity_threshold: threshold,
+                        substring,
+                        kind: if regex {
+                            PhraseKind::Regex
+                        } else {
+                            PhraseKind::Literal
+                        },
+                        allowed_speakers: allow_speaker,
+                        denied_speakers: deny_speaker,
+                    })
+                    .send()
+                    .await
+                    .map(|resp| resp.error_for_status())??;
+                let phrase = response.json::<Phrase>().await?;
+                println!("{}", serde_json::to_string_pretty(&phrase)?);
+                Ok(())
+            }
+            PhraseCommand::Edit {
+                phrase_id,
+                phrase,
+                add_clip,
+                remove_clip,
+                threshold,
+                default_threshold,
+                substring,
+                regex,
+                allow_speaker,
+                deny_speaker,
+                any_speaker,
+            } => {
+                let url = opts.url.join(&format!("/v1/phrases/{phrase_id}"))?;
+                let old_phrase = client
+                    .get(url.clone())
+                    .basic_auth(&opts.user, Some(&opts.password))
+                    .send()
+                    .await
+                    .map(|resp| resp.error_for_status())??
+                    .json::<Phrase>()
+                    .await?;
+                let kind = match regex {
+                    Some(true) => PhraseKind::Regex,
+                    Some(false) => PhraseKind::Literal,
+                    None => old_phrase.kind,
+                };
+                let mut clips = old_phrase.clips;
+                clips.retain(|clip| !remove_clip.iter().any(|id| id.to_string() == *clip));
+                for clip in add_clip.iter().map(Uuid::to_string) {
+                    if !clips.contains(&clip) {
+                        clips.push(clip);
+                    }
+                }
+                let similarity_threshold = match default_threshold {
+                    true => None,
+                    false => threshold.or(old_phrase.similarity_threshold),
+                };
+                let (allowed_speakers, denied_speakers) = if any_speaker {
+                    (vec![], vec![])
+                } else {
+                    (
+                        Some(allow_speaker)
+                            .filter(|speakers| !speakers.is_empty())
+                            .unwrap_or(old_phrase.allowed_speakers),
+                        Some(deny_speaker)
+                            .filter(|speakers| !speakers.is_empty())
+                            .unwrap_or(old_phrase.denied_speakers),
+                    )
+                };
+                let response = client
+                    .put(url)
+                    .basic_auth(opts.user, Some(opts.password))
+                    .json(&CreatePhrase {
+                        clips,
+                        phrase: phrase.unwrap_or(old_phrase.phrase),
+                        similarity_threshold,
+                        substring: substring.unwrap_or(old_phrase.substring),
+                        kind,
+                        allowed_speakers,
+                        denied_speakers,
                     })
                     .send()
                     .await
@@ -291,10 +810,99 @@ async fn process_command(opts: Cli) -> Result<(), Error> {
                 println!("{}", serde_json::to_string_pretty(&phrase)?);
                 Ok(())
             }
+            PhraseCommand::Lint { min_length } => {
+                let url = opts.url.join("/v1/phrases/conflicts")?;
+                let response = client
+                    .get(url)
+                    .basic_auth(opts.user, Some(opts.password))
+                    .query(&ConflictsQuery {
+                        min_length: Some(min_length),
+                    })
+                    .send()
+                    .await
+                    .map(|resp| resp.error_for_status())??;
+                let conflicts = response.json::<PhraseConflicts>().await?;
+                display_conflicts(&conflicts);
+                Ok(())
+            }
+            PhraseCommand::Remove { phrase_id, exclude } => {
+                let resource = if exclude { "exclusions" } else { "phrases" };
+                let url = opts.url.join(&format!("/v1/{resource}/{phrase_id}"))?;
+                let response = client
+                    .delete(url)
+                    .basic_auth(opts.user, Some(opts.password))
+                    .send()
+                    .await
+                    .map(|resp| resp.error_for_status())??;
+                if exclude {
+                    let exclusion = response.json::<Exclusion>().await?;
+                    println!("{}", serde_json::to_string_pretty(&exclusion)?);
+                } else {
+                    let phrase = response.json::<Phrase>().await?;
+                    println!("{}", serde_json::to_string_pretty(&phrase)?);
+                }
+                Ok(())
+            }
+            PhraseCommand::Show { phrase_id } => {
+                let url = opts.url.join(&format!("/v1/phrases/{phrase_id}"))?;
+                let response = client
+                    .get(url)
+                    .basic_auth(opts.user, Some(opts.password))
+                    .send()
+                    .await
+                    .map(|resp| resp.error_for_status())??;
+                let phrase = response.json::<Phrase>().await?;
+                println!("{}", serde_json::to_string_pretty(&phrase)?);
+                Ok(())
+            }
         },
     }
 }
 
+/// Parse a command-line argument into one of the API's enums using its serialized name.
+fn parse_api_enum<T: serde::de::DeserializeOwned>(value: &str) -> Result<T, String> {
+    serde_json::from_value(serde_json::Value::String(value.to_string()))
+        .map_err(|_| format!("'{value}' is not a valid choice"))
+}
+
+/// Parse an availability window; see "clip edit --help" for the format.
+fn parse_window(value: &str) -> Result<AvailabilityWindow, String> {
+    let mut window = AvailabilityWindow::default();
+    for part in value.split_whitespace() {
+        let (key, value) = part
+            .split_once('=')
+            .ok_or_else(|| format!("'{part}' should be in the form key=value"))?;
+        match key {
+            "dates" => {
+                let (start, end) = value
+                    .split_once("..")
+                    .ok_or_else(|| format!("'{value}' should be in the form MM-DD..MM-DD"))?;
+                window.start_date = Some(start.to_string());
+                window.end_date = Some(end.to_string());
+            }
+            "days" => {
+                window.weekdays = value
+                    .split(',')
+                    .map(|day| {
+                        day.parse::<Weekday>()
+                            .map_err(|_| format!("'{day}' is not a day of the week"))
+                    })
+                    .collect::<Result<_, _>>()?;
+            }
+            "hours" => {
+                let (start, end) = value
+                    .split_once("..")
+                    .and_then(|(start, end)| Some((start.parse().ok()?, end.parse().ok()?)))
+                    .ok_or_else(|| format!("'{value}' should be in the form H..H"))?;
+                window.start_hour = Some(start);
+                window.end_hour = Some(end);
+            }
+            _ => return Err(format!("'{key}' should be one of dates, days, or hours")),
+        }
+    }
+    Ok(window)
+}
+
 fn display_clips(clips: &Clips) {
     let mut table = prettytable::Table::new();
     table.add_row(prettytable::Row::new(vec![
@@ -302,6 +910,9 @@ fn display_clips(clips: &Clips) {
         prettytable::Cell::new("Created").with_style(prettytable::Attr::Bold),
         prettytable::Cell::new("Last Played").with_style(prettytable::Attr::Bold),
         prettytable::Cell::new("Plays").with_style(prettytable::Attr::Bold),
+        prettytable::Cell::new("Weight").with_style(prettytable::Attr::Bold),
+        prettytable::Cell::new("Enabled").with_style(prettytable::Attr::Bold),
+        prettytable::Cell::new("Tags").with_style(prettytable::Attr::Bold),
         prettytable::Cell::new("Title").with_style(prettytable::Attr::Bold),
         prettytable::Cell::new("Phrases").with_style(prettytable::Attr::Bold),
     ]));
@@ -311,6 +922,9 @@ fn display_clips(clips: &Clips) {
             prettytable::Cell::new(clip.created_on.trunc_subsecs(0).to_string().as_str()),
             prettytable::Cell::new(clip.last_played.trunc_subsecs(0).to_string().as_str()),
             prettytable::Cell::new(clip.plays.to_string().as_str()),
+            prettytable::Cell::new(clip.weight.to_string().as_str()),
+            prettytable::Cell::new(if clip.enabled { "yes" } else { "no" }),
+            prettytable::Cell::new(clip.tags.join(", ").as_str()),
             prettytable::Cell::new(clip.title.chars().take(64).collect::<String>().as_str()),
             prettytable::Cell::new(
                 clip.phrases
@@ -333,3 +947,183 @@ fn display_clips(clips: &Clips) {
 
     table.printstd();
 }
+
+fn display_match_result(result: &MatchResult) {
+    println!("Normalized text: {}", result.normalized_text);
+    if result.bypasses_rate_limit {
+        println!("Chance of playing a clip: 100% (rate limiting bypassed)");
+    } else {
+        println!(
+            "Chance of playing a clip: {:.0}%",
+            result.play_chance * 100.0
+        );
+    }
+
+    let mut table = prettytable::Table::new();
+    table.add_row(prettytable::Row::new(vec![
+        prettytable::Cell::new("ID").with_style(prettytable::Attr::Bold),
+        prettytable::Cell::new("Title").with_style(prettytable::Attr::Bold),
+        prettytable::Cell::new("Matched").with_style(prettytable::Attr::Bold),
+        prettytable::Cell::new("Score").with_style(prettytable::Attr::Bold),
+        prettytable::Cell::new("Weight").with_style(prettytable::Attr::Bold),
+        prettytable::Cell::new("Cooling Down").with_style(prettytable::Attr::Bold),
+        prettytable::Cell::new("Excluded By").with_style(prettytable::Attr::Bold),
+        prettytable::Cell::new("Muted").with_style(prettytable::Attr::Bold),
+        prettytable::Cell::new("Unavailable").with_style(prettytable::Attr::Bold),
+    ]));
+    for candidate in result.candidates.iter() {
+        table.add_row(prettytable::Row::new(vec![
+            prettytable::Cell::new(candidate.clip.uuid.as_str()),
+            prettytable::Cell::new(
+                candidate
+                    .clip
+                    .title
+                    .chars()
+                    .take(64)
+                    .collect::<String>()
+                    .as_str(),
+            ),
+            prettytable::Cell::new(
+                candidate
+                    .phrase
+                    .chars()
+                    .take(64)
+                    .collect::<String>()
+                    .as_str(),
+            ),
+            prettytable::Cell::new(format!("{:.2}", candidate.score).as_str()),
+            prettytable::Cell::new(candidate.clip.weight.to_string().as_str()),
+            prettytable::Cell::new(if candidate.cooling_down { "yes" } else { "no" }),
+            prettytable::Cell::new(candidate.excluded_by.as_deref().unwrap_or("")),
+            prettytable::Cell::new(if candidate.muted { "yes" } else { "no" }),
+            prettytable::Cell::new(if candidate.unavailable { "yes" } else { "no" }),
+        ]));
+    }
+
+    table.printstd();
+}
+
+fn display_search_results(results: &ClipSearchResults) {
+    let mut table = prettytable::Table::new();
+    table.add_row(prettytable::Row::new(vec![
+        prettytable::Cell::new("ID").with_style(prettytable::Attr::Bold),
+        prettytable::Cell::new("Title").with_style(prettytable::Attr::Bold),
+        prettytable::Cell::new("Match").with_style(prettytable::Attr::Bold),
+    ]));
+    for result in results.results.iter() {
+        table.add_row(prettytable::Row::new(vec![
+            prettytable::Cell::new(result.clip.uuid.as_str()),
+            prettytable::Cell::new(
+                result
+                    .clip
+                    .title
+                    .chars()
+                    .take(64)
+                    .collect::<String>()
+                    .as_str(),
+            ),
+            prettytable::Cell::new(result.snippet.replace('\n', " ").as_str()),
+        ]));
+    }
+
+    table.printstd();
+}
+
+fn display_conflicts(conflicts: &PhraseConflicts) {
+    if conflicts.conflicts.is_empty() {
+        println!("No conflicting phrases found");
+        return;
+    }
+
+    let mut table = prettytable::Table::new();
+    table.add_row(prettytable::Row::new(vec![
+        prettytable::Cell::new("Problem").with_style(prettytable::Attr::Bold),
+        prettytable::Cell::new("Phrase ID").with_style(prettytable::Attr::Bold),
+        prettytable::Cell::new("Phrase").with_style(prettytable::Attr::Bold),
+        prettytable::Cell::new("Conflicts With").with_style(prettytable::Attr::Bold),
+        prettytable::Cell::new("Conflicting Text").with_style(prettytable::Attr::Bold),
+    ]));
+    for conflict in conflicts.conflicts.iter() {
+        let problem = match conflict.kind {
+            ConflictKind::Duplicate => "Duplicate of phrase",
+            ConflictKind::Substring => "Part of phrase",
+            ConflictKind::TooShort => "Too short",
+            ConflictKind::SpeechDetected => "Part of clip's speech",
+        };
+        table.add_row(prettytable::Row::new(vec![
+            prettytable::Cell::new(problem),
+            prettytable::Cell::new(conflict.phrase.as_str()),
+            prettytable::Cell::new(conflict.text.chars().take(64).collect::<String>().as_str()),
+            prettytable::Cell::new(conflict.other.as_deref().unwrap_or_default()),
+            prettytable::Cell::new(
+                conflict
+                    .other_text
+                    .as_deref()
+                    .unwrap_or_default()
+                    .chars()
+                    .take(64)
+                    .collect::<String>()
+                    .as_str(),
+            ),
+        ]));
+    }
+
+    table.printstd();
+}
+
+fn display_stats(stats: &Stats) {
+    let header = |titles: &[&str]| {
+        prettytable::Row::new(
+            titles
+                .iter()
+                .map(|title| prettytable::Cell::new(title).with_style(prettytable::Attr::Bold))
+                .collect(),
+        )
+    };
+
+    println!("Top clips from {} to {}", stats.since, stats.until);
+    let mut table = prettytable::Table::new();
+    table.add_row(header(&["ID", "Title", "Plays"]));
+    for clip in stats.top_clips.iter() {
+        table.add_row(prettytable::Row::new(vec![
+            prettytable::Cell::new(clip.uuid.as_str()),
+            prettytable::Cell::new(clip.title.chars().take(64).collect::<String>().as_str()),
+            prettytable::Cell::new(clip.plays.to_string().as_str()),
+        ]));
+    }
+    table.printstd();
+
+    println!("\nTop phrases from {} to {}", stats.since, stats.until);
+    let mut table = prettytable::Table::new();
+    table.add_row(header(&["Phrase", "Plays"]));
+    for phrase in stats.top_phrases.iter() {
+        table.add_row(prettytable::Row::new(vec![
+            prettytable::Cell::new(phrase.phrase.chars().take(64).collect::<String>().as_str()),
+            prettytable::Cell::new(phrase.plays.to_string().as_str()),
+        ]));
+    }
+    table.printstd();
+
+    println!("\nPlays per day");
+    let mut table = prettytable::Table::new();
+    table.add_row(header(&["Day", "Plays"]));
+    for day in stats.plays_per_day.iter() {
+        table.add_row(prettytable::Row::new(vec![
+            prettytable::Cell::new(day.day.to_string().as_str()),
+            prettytable::Cell::new(day.plays.to_string().as_str()),
+        ]));
+    }
+    table.printstd();
+
+    println!("\nClips that have never been played");
+    let mut table = prettytable::Table::new();
+    table.add_row(header(&["ID", "Created", "Title"]));
+    for clip in stats.never_played.iter() {
+        table.add_row(prettytable::Row::new(vec![
+            prettytable::Cell::new(clip.uuid.as_str()),
+            prettytable::Cell::new(clip.created_on.trunc_subsecs(0).to_string().as_str()),
+            prettytable::Cell::new(clip.title.chars().take(64).collect::<String>().as_str()),
+        ]));
+    }
+    table.printstd();
+}
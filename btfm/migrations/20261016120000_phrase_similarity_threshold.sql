@@ -0,0 +1,2 @@
+-- An optional per-phrase override for the minimum fuzzy matching similarity.
+ALTER TABLE "clip_phrases" ADD COLUMN "similarity_threshold" REAL;
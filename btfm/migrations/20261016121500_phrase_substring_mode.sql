@@ -0,0 +1,2 @@
+-- Phrases match whole words by default; this opts a phrase into the old substring behavior.
+ALTER TABLE "clip_phrases" ADD COLUMN "substring" BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,2 @@
+-- Phrases are either literal text or a regular expression.
+ALTER TABLE "clip_phrases" ADD COLUMN "kind" TEXT NOT NULL DEFAULT 'literal' CHECK ("kind" IN ('literal', 'regex'));
@@ -0,0 +1,2 @@
+-- How likely a clip is to be chosen relative to other clips; 0 means it is never played at random.
+ALTER TABLE "clips" ADD COLUMN "weight" REAL NOT NULL DEFAULT 1.0 CHECK ("weight" >= 0);
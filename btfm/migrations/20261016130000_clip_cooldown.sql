@@ -0,0 +1,2 @@
+-- An optional per-clip override for how long, in seconds, a clip must wait before replaying.
+ALTER TABLE "clips" ADD COLUMN "cooldown" INTEGER CHECK ("cooldown" >= 0);
@@ -0,0 +1,14 @@
+-- A record of every time a clip was played, and what caused it.
+CREATE TABLE IF NOT EXISTS "plays" (
+    "uuid" TEXT NOT NULL PRIMARY KEY,
+    "clip" TEXT NOT NULL,
+    "played_on" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "triggered_by" TEXT NOT NULL CHECK ("triggered_by" IN ('phrase', 'random', 'api')),
+    "user_id" BIGINT,
+    "ssrc" BIGINT,
+    "transcript" TEXT,
+    "phrase" TEXT,
+    FOREIGN KEY (clip) REFERENCES clips(uuid) ON DELETE CASCADE ON UPDATE NO ACTION
+);
+CREATE INDEX "plays_clip_index" ON "plays" ("clip");
+CREATE INDEX "plays_played_on_index" ON "plays" ("played_on");
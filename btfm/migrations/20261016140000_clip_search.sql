@@ -0,0 +1,69 @@
+-- Full-text search over clips and their phrases.
+--
+-- The search table is kept in sync with the clips and clip_phrases tables by triggers. The
+-- phrases column holds all of a clip's phrases, one per line.
+CREATE VIRTUAL TABLE "clips_search" USING fts5(
+    "uuid" UNINDEXED,
+    "title",
+    "description",
+    "speech_detected",
+    "phrases",
+    tokenize = 'porter unicode61'
+);
+
+INSERT INTO "clips_search" ("uuid", "title", "description", "speech_detected", "phrases")
+SELECT
+    uuid,
+    title,
+    description,
+    speech_detected,
+    (SELECT group_concat(phrase, char(10)) FROM clip_phrases WHERE clip_phrases.clip = clips.uuid)
+FROM clips;
+
+CREATE TRIGGER "clips_search_insert" AFTER INSERT ON "clips" BEGIN
+    INSERT INTO "clips_search" ("uuid", "title", "description", "speech_detected", "phrases")
+    VALUES (
+        NEW.uuid,
+        NEW.title,
+        NEW.description,
+        NEW.speech_detected,
+        (SELECT group_concat(phrase, char(10)) FROM clip_phrases WHERE clip_phrases.clip = NEW.uuid)
+    );
+END;
+
+CREATE TRIGGER "clips_search_update" AFTER UPDATE OF "uuid", "title", "description", "speech_detected" ON "clips" BEGIN
+    DELETE FROM "clips_search" WHERE uuid = OLD.uuid;
+    INSERT INTO "clips_search" ("uuid", "title", "description", "speech_detected", "phrases")
+    VALUES (
+        NEW.uuid,
+        NEW.title,
+        NEW.description,
+        NEW.speech_detected,
+        (SELECT group_concat(phrase, char(10)) FROM clip_phrases WHERE clip_phrases.clip = NEW.uuid)
+    );
+END;
+
+CREATE TRIGGER "clips_search_delete" AFTER DELETE ON "clips" BEGIN
+    DELETE FROM "clips_search" WHERE uuid = OLD.uuid;
+END;
+
+CREATE TRIGGER "clips_search_phrase_insert" AFTER INSERT ON "clip_phrases" BEGIN
+    UPDATE "clips_search"
+    SET phrases = (SELECT group_concat(phrase, char(10)) FROM clip_phrases WHERE clip_phrases.clip = NEW.clip)
+    WHERE uuid = NEW.clip;
+END;
+
+CREATE TRIGGER "clips_search_phrase_update" AFTER UPDATE ON "clip_phrases" BEGIN
+    UPDATE "clips_search"
+    SET phrases = (SELECT group_concat(phrase, char(10)) FROM clip_phrases WHERE clip_phrases.clip = OLD.clip)
+    WHERE uuid = OLD.clip;
+    UPDATE "clips_search"
+    SET phrases = (SELECT group_concat(phrase, char(10)) FROM clip_phrases WHERE clip_phrases.clip = NEW.clip)
+    WHERE uuid = NEW.clip;
+END;
+
+CREATE TRIGGER "clips_search_phrase_delete" AFTER DELETE ON "clip_phrases" BEGIN
+    UPDATE "clips_search"
+    SET phrases = (SELECT group_concat(phrase, char(10)) FROM clip_phrases WHERE clip_phrases.clip = OLD.clip)
+    WHERE uuid = OLD.clip;
+END;
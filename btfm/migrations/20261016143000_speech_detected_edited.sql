@@ -0,0 +1,2 @@
+-- Whether an administrator has corrected the detected speech by hand.
+ALTER TABLE "clips" ADD COLUMN "speech_detected_edited" BOOLEAN NOT NULL DEFAULT FALSE;
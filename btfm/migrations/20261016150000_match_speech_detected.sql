@@ -0,0 +1,2 @@
+-- Whether a clip's detected speech triggers it, in addition to its phrases.
+ALTER TABLE "clips" ADD COLUMN "match_speech_detected" BOOLEAN NOT NULL DEFAULT TRUE;
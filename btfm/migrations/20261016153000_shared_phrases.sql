@@ -0,0 +1,105 @@
+-- Phrases can be shared between clips, so a single phrase can trigger a pool of clips.
+--
+-- Identical phrases in clip_phrases are merged, keeping the ID of the oldest one. A phrase
+-- only exists while it's linked to at least one clip.
+CREATE TABLE IF NOT EXISTS "phrases" (
+    "uuid" TEXT NOT NULL PRIMARY KEY,
+    "phrase" TEXT NOT NULL,
+    "similarity_threshold" REAL,
+    "substring" BOOLEAN NOT NULL DEFAULT FALSE,
+    "kind" TEXT NOT NULL DEFAULT 'literal' CHECK ("kind" IN ('literal', 'regex'))
+);
+
+CREATE TABLE IF NOT EXISTS "clip_phrase_links" (
+    "clip" TEXT NOT NULL,
+    "phrase" TEXT NOT NULL,
+    PRIMARY KEY ("clip", "phrase"),
+    FOREIGN KEY (clip) REFERENCES clips(uuid) ON DELETE CASCADE ON UPDATE NO ACTION,
+    FOREIGN KEY (phrase) REFERENCES phrases(uuid) ON DELETE CASCADE ON UPDATE NO ACTION
+);
+CREATE INDEX IF NOT EXISTS "clip_phrase_links_phrase" ON "clip_phrase_links" ("phrase");
+
+INSERT INTO "phrases" ("uuid", "phrase", "similarity_threshold", "substring", "kind")
+SELECT uuid, phrase, similarity_threshold, substring, kind
+FROM clip_phrases
+WHERE rowid IN (
+    SELECT min(rowid) FROM clip_phrases GROUP BY phrase, similarity_threshold, substring, kind
+);
+
+INSERT OR IGNORE INTO "clip_phrase_links" ("clip", "phrase")
+SELECT clip_phrases.clip, phrases.uuid
+FROM clip_phrases
+JOIN phrases
+    ON phrases.phrase = clip_phrases.phrase
+    AND phrases.similarity_threshold IS clip_phrases.similarity_threshold
+    AND phrases.substring = clip_phrases.substring
+    AND phrases.kind = clip_phrases.kind;
+
+DROP TRIGGER "clips_search_insert";
+DROP TRIGGER "clips_search_update";
+DROP TRIGGER "clips_search_phrase_insert";
+DROP TRIGGER "clips_search_phrase_update";
+DROP TRIGGER "clips_search_phrase_delete";
+DROP TABLE "clip_phrases";
+
+CREATE TRIGGER "phrases_delete_unlinked" AFTER DELETE ON "clip_phrase_links"
+WHEN NOT EXISTS (SELECT 1 FROM clip_phrase_links WHERE phrase = OLD.phrase)
+BEGIN
+    DELETE FROM "phrases" WHERE uuid = OLD.phrase;
+END;
+
+-- Keep the phrases in the search table up to date.
+CREATE TRIGGER "clips_search_insert" AFTER INSERT ON "clips" BEGIN
+    INSERT INTO "clips_search" ("uuid", "title", "description", "speech_detected", "phrases")
+    VALUES (NEW.uuid, NEW.title, NEW.description, NEW.speech_detected, NULL);
+END;
+
+CREATE TRIGGER "clips_search_update" AFTER UPDATE OF "uuid", "title", "description", "speech_detected" ON "clips" BEGIN
+    DELETE FROM "clips_search" WHERE uuid = OLD.uuid;
+    INSERT INTO "clips_search" ("uuid", "title", "description", "speech_detected", "phrases")
+    VALUES (
+        NEW.uuid,
+        NEW.title,
+        NEW.description,
+        NEW.speech_detected,
+        (
+            SELECT group_concat(phrases.phrase, char(10))
+            FROM clip_phrase_links
+            JOIN phrases ON phrases.uuid = clip_phrase_links.phrase
+            WHERE clip_phrase_links.clip = NEW.uuid
+        )
+    );
+END;
+
+CREATE TRIGGER "clips_search_link_insert" AFTER INSERT ON "clip_phrase_links" BEGIN
+    UPDATE "clips_search"
+    SET phrases = (
+        SELECT group_concat(phrases.phrase, char(10))
+        FROM clip_phrase_links
+        JOIN phrases ON phrases.uuid = clip_phrase_links.phrase
+        WHERE clip_phrase_links.clip = NEW.clip
+    )
+    WHERE uuid = NEW.clip;
+END;
+
+CREATE TRIGGER "clips_search_link_delete" AFTER DELETE ON "clip_phrase_links" BEGIN
+    UPDATE "clips_search"
+    SET phrases = (
+        SELECT group_concat(phrases.phrase, char(10))
+        FROM clip_phrase_links
+        JOIN phrases ON phrases.uuid = clip_phrase_links.phrase
+        WHERE clip_phrase_links.clip = OLD.clip
+    )
+    WHERE uuid = OLD.clip;
+END;
+
+CREATE TRIGGER "clips_search_phrase_update" AFTER UPDATE OF "phrase" ON "phrases" BEGIN
+    UPDATE "clips_search"
+    SET phrases = (
+        SELECT group_concat(phrases.phrase, char(10))
+        FROM clip_phrase_links
+        JOIN phrases ON phrases.uuid = clip_phrase_links.phrase
+        WHERE clip_phrase_links.clip = clips_search.uuid
+    )
+    WHERE uuid IN (SELECT clip FROM clip_phrase_links WHERE phrase = NEW.uuid);
+END;
@@ -0,0 +1,8 @@
+-- Phrases that stop a clip from being played when they appear in the transcript.
+CREATE TABLE IF NOT EXISTS "clip_exclusions" (
+    "uuid" TEXT NOT NULL PRIMARY KEY,
+    "clip" TEXT NOT NULL,
+    "phrase" TEXT NOT NULL,
+    FOREIGN KEY (clip) REFERENCES clips(uuid) ON DELETE CASCADE ON UPDATE NO ACTION
+);
+CREATE INDEX IF NOT EXISTS "clip_exclusions_clip" ON "clip_exclusions" ("clip");
@@ -0,0 +1,2 @@
+-- Disabled clips are never played, but are kept along with their audio and phrases.
+ALTER TABLE "clips" ADD COLUMN "enabled" BOOLEAN NOT NULL DEFAULT TRUE;
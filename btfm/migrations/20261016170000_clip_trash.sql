@@ -0,0 +1,4 @@
+-- Removed clips are kept in the trash, with their audio in the trash directory, until they're
+-- restored or purged.
+ALTER TABLE "clips" ADD COLUMN "deleted_on" TIMESTAMP;
+CREATE INDEX IF NOT EXISTS "clips_deleted_on" ON "clips" ("deleted_on");
@@ -0,0 +1,14 @@
+-- Tags group clips so whole groups can be muted, or played exclusively, by configuration.
+CREATE TABLE IF NOT EXISTS "tags" (
+    "uuid" TEXT NOT NULL PRIMARY KEY,
+    "name" TEXT NOT NULL UNIQUE
+);
+
+CREATE TABLE IF NOT EXISTS "clip_tags" (
+    "clip" TEXT NOT NULL,
+    "tag" TEXT NOT NULL,
+    PRIMARY KEY ("clip", "tag"),
+    FOREIGN KEY (clip) REFERENCES clips(uuid) ON DELETE CASCADE ON UPDATE NO ACTION,
+    FOREIGN KEY (tag) REFERENCES tags(uuid) ON DELETE CASCADE ON UPDATE NO ACTION
+);
+CREATE INDEX IF NOT EXISTS "clip_tags_tag" ON "clip_tags" ("tag");
@@ -0,0 +1,17 @@
+-- Windows of time when a clip can play; clips without any windows can always play.
+--
+-- Dates are a yearly "MM-DD" range, weekdays are a comma-separated list of abbreviated day
+-- names (e.g. "Mon,Tue"), and hours are a range from the start hour up to the end hour.
+-- Ranges that end before they start wrap around.
+CREATE TABLE IF NOT EXISTS "clip_availability" (
+    "clip" TEXT NOT NULL,
+    "start_date" TEXT,
+    "end_date" TEXT,
+    "weekdays" TEXT NOT NULL DEFAULT '',
+    "start_hour" INTEGER,
+    "end_hour" INTEGER,
+    CHECK (("start_date" IS NULL) = ("end_date" IS NULL)),
+    CHECK (("start_hour" IS NULL) = ("end_hour" IS NULL)),
+    FOREIGN KEY (clip) REFERENCES clips(uuid) ON DELETE CASCADE ON UPDATE NO ACTION
+);
+CREATE INDEX IF NOT EXISTS "clip_availability_clip" ON "clip_availability" ("clip");
@@ -0,0 +1,11 @@
+-- Phrases can be restricted to, or from, particular Discord users.
+--
+-- If a phrase has any allowed speakers, only they can trigger it; denied speakers can never
+-- trigger it.
+CREATE TABLE IF NOT EXISTS "phrase_speakers" (
+    "phrase" TEXT NOT NULL,
+    "user_id" INTEGER NOT NULL,
+    "allowed" BOOLEAN NOT NULL,
+    PRIMARY KEY ("phrase", "user_id"),
+    FOREIGN KEY (phrase) REFERENCES phrases(uuid) ON DELETE CASCADE ON UPDATE NO ACTION
+);
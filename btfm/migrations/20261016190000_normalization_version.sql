@@ -0,0 +1,6 @@
+-- The version of the text normalization rules that phrases, exclusions, and detected speech
+-- were stored with. When the server's rules are newer, the text is re-normalized at startup.
+CREATE TABLE IF NOT EXISTS "normalization" (
+    "version" INTEGER NOT NULL
+);
+INSERT INTO "normalization" ("version") VALUES (0);
@@ -0,0 +1,220 @@
+// SPDX-License-Identifier: GPL-2.0-or-later
+//! Decide whether clips can play right now according to their availability windows.
+//!
+//! Windows are evaluated in the configured timezone so "before noon" means noon where the
+//! server's users are, not noon UTC.
+use std::collections::HashMap;
+
+use btfm_api_structs::AvailabilityWindow;
+use chrono::{Datelike, NaiveDateTime, Timelike};
+
+/// The current time in the configured timezone.
+pub fn local_now() -> NaiveDateTime {
+    let config = crate::CONFIG.get().expect("Initialize the config");
+    chrono::Utc::now()
+        .with_timezone(&config.timezone)
+        .naive_local()
+}
+
+/// Whether the clip can play at the given time.
+///
+/// # Arguments
+///
+/// `availability` - The availability windows of clips, as returned by
+///                  [`crate::db::availability_for_clips`].
+///
+/// `time` - The time, in the configured timezone; see [`local_now`].
+pub fn is_available(
+    availability: &HashMap<String, Vec<AvailabilityWindow>>,
+    clip: &str,
+    time: NaiveDateTime,
+) -> bool {
+    availability
+        .get(clip)
+        .is_none_or(|windows| windows.is_empty() || windows.iter().any(|w| is_open(w, time)))
+}
+
+/// Whether the time falls within the window.
+pub fn is_open(window: &AvailabilityWindow, time: NaiveDateTime) -> bool {
+    let dates = match (&window.start_date, &window.end_date) {
+        (Some(start), Some(end)) => match (parse_month_day(start), parse_month_day(end)) {
+            (Some(start), Some(end)) => in_range(start, end, (time.month(), time.day()), true),
+            _ => false,
+        },
+        _ => true,
+    };
+    let weekdays = window.weekdays.is_empty() || window.weekdays.contains(&time.weekday());
+    let hours = match (window.start_hour, window.end_hour) {
+        (Some(start), Some(end)) => in_range(start, end, time.hour(), false),
+        _ => true,
+    };
+    dates && weekdays && hours
+}
+
+/// Windows must set both ends of their ranges, or neither, and the ends must be valid.
+pub fn is_valid(window: &AvailabilityWindow) -> bool {
+    let dates = match (&window.start_date, &window.end_date) {
+        (Some(start), Some(end)) => {
+            parse_month_day(start).is_some() && parse_month_day(end).is_some()
+        }
+        (None, None) => true,
+        _ => false,
+    };
+    let hours = match (window.start_hour, window.end_hour) {
+        (Some(start), Some(end)) => start < 24 && (1..=24).contains(&end) && start != end,
+        (None, None) => true,
+        _ => false,
+    };
+    dates && hours
+}
+
+/// Parse a "MM-DD" date into a month and day; February 29th is allowed.
+fn parse_month_day(date: &str) -> Option<(u32, u32)> {
+    let (month, day) = date.split_once('-')?;
+    let (month, day) = (month.parse().ok()?, day.parse().ok()?);
+    chrono::NaiveDate::from_ymd_opt(2000, month, day).map(|_| (month, day))
+}
+
+/// Whether the value is in the range, wrapping around if the range ends before it starts.
+fn in_range<T: PartialOrd>(start: T, end: T, value: T, inclusive_end: bool) -> bool {
+    let before_end = if inclusive_end {
+        value <= end
+    } else {
+        value < end
+    };
+    if start <= end {
+        start <= value && before_end
+    } else {
+        start <= value || before_end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::{NaiveDate, Weekday};
+
+    fn at(month: u32, day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, month, day)
+            .unwrap()
+            .and_hms_opt(hour, 30, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn date_range() {
+        let december = AvailabilityWindow {
+            start_date: Some("12-01".into()),
+            end_date: Some("12-31".into()),
+            ..Default::default()
+        };
+        assert!(is_open(&december, at(12, 1, 0)));
+        assert!(is_open(&december, at(12, 31, 23)));
+        assert!(!is_open(&december, at(11, 30, 12)));
+        assert!(!is_open(&december, at(1, 1, 12)));
+    }
+
+    #[test]
+    fn date_range_wraps() {
+        let holidays = AvailabilityWindow {
+            start_date: Some("12-15".into()),
+            end_date: Some("01-05".into()),
+            ..Default::default()
+        };
+        assert!(is_open(&holidays, at(12, 20, 12)));
+        assert!(is_open(&holidays, at(1, 5, 12)));
+        assert!(!is_open(&holidays, at(1, 6, 12)));
+        assert!(!is_open(&holidays, at(7, 4, 12)));
+    }
+
+    #[test]
+    fn hours() {
+        let morning = AvailabilityWindow {
+            start_hour: Some(0),
+            end_hour: Some(12),
+            ..Default::default()
+        };
+        assert!(is_open(&morning, at(3, 1, 11)));
+        assert!(!is_open(&morning, at(3, 1, 12)));
+
+        let late_night = AvailabilityWindow {
+            start_hour: Some(22),
+            end_hour: Some(2),
+            ..Default::default()
+        };
+        assert!(is_open(&late_night, at(3, 1, 23)));
+        assert!(is_open(&late_night, at(3, 1, 1)));
+        assert!(!is_open(&late_night, at(3, 1, 2)));
+        assert!(!is_open(&late_night, at(3, 1, 12)));
+    }
+
+    #[test]
+    fn all_conditions_hold() {
+        // 2024-03-02 is a Saturday.
+        let weekend_mornings = AvailabilityWindow {
+            weekdays: vec![Weekday::Sat, Weekday::Sun],
+            start_hour: Some(6),
+            end_hour: Some(12),
+            ..Default::default()
+        };
+        assert!(is_open(&weekend_mornings, at(3, 2, 8)));
+        assert!(!is_open(&weekend_mornings, at(3, 2, 13)));
+        assert!(!is_open(&weekend_mornings, at(3, 4, 8)));
+    }
+
+    #[test]
+    fn any_window() {
+        let clip = "clip".to_string();
+        let mut availability = HashMap::new();
+        assert!(is_available(&availability, &clip, at(6, 1, 12)));
+
+        availability.insert(
+            clip.clone(),
+            vec![
+                AvailabilityWindow {
+                    start_date: Some("12-01".into()),
+                    end_date: Some("12-31".into()),
+                    ..Default::default()
+                },
+                AvailabilityWindow {
+                    start_hour: Some(0),
+                    end_hour: Some(12),
+                    ..Default::default()
+                },
+            ],
+        );
+        assert!(is_available(&availability, &clip, at(12, 24, 18)));
+        assert!(is_available(&availability, &clip, at(6, 1, 8)));
+        assert!(!is_available(&availability, &clip, at(6, 1, 18)));
+    }
+
+    #[test]
+    fn validation() {
+        assert!(is_valid(&AvailabilityWindow::default()));
+        assert!(is_valid(&AvailabilityWindow {
+            start_date: Some("02-29".into()),
+            end_date: Some("03-01".into()),
+            ..Default::default()
+        }));
+        assert!(!is_valid(&AvailabilityWindow {
+            start_date: Some("02-30".into()),
+            end_date: Some("03-01".into()),
+            ..Default::default()
+        }));
+        assert!(!is_valid(&AvailabilityWindow {
+            start_date: Some("12-01".into()),
+            ..Default::default()
+        }));
+        assert!(!is_valid(&AvailabilityWindow {
+            start_hour: Some(24),
+            end_hour: Some(2),
+            ..Default::default()
+        }));
+        assert!(!is_valid(&AvailabilityWindow {
+            start_hour: Some(8),
+            end_hour: Some(8),
+            ..Default::default()
+        }));
+    }
+}
@@ -13,6 +13,8 @@ use sqlx::{
 use tracing::{info, instrument, Instrument};
 
 use btfm::discord::{text::Handler, BtfmData};
+use btfm::retranscribe::{retranscribe, RetranscribeJob};
+use btfm::transcribe::Transcriber;
 use btfm::{cli, db, Error};
 
 static MIGRATIONS: sqlx::migrate::Migrator = sqlx::migrate!("./migrations/");
@@ -57,6 +59,11 @@ async fn migrate(opts: &cli::Btfm) -> anyhow::Result<Pool<Sqlite>> {
         .run(&db_pool)
         .await
         .context("Failed to migrate database to latest versions!")?;
+    let mut transaction = db_pool.begin().await?;
+    db::renormalize(&mut transaction)
+        .await
+        .context("Failed to re-normalize phrases")?;
+    transaction.commit().await?;
 
     Ok(db_pool)
 }
@@ -77,11 +84,12 @@ async fn process_command(opts: cli::Btfm, db_pool: Pool<Sqlite>) -> Result<(), E
                 .await?;
 
             let http_handle = axum_server::Handle::new();
-            let transcriber = {
+            let (transcriber, phrase_index) = {
                 let mut data = client.data.write().await;
                 let btfm_data = BtfmData::new().await;
                 let transcriber = btfm_data.transcriber.clone();
                 let web_transcriber = btfm_data.transcriber.clone();
+                let phrase_index = btfm_data.phrase_index.clone();
                 let handle = http_handle.clone();
                 data.insert::<BtfmData>(Arc::new(Mutex::new(btfm_data)));
 
@@ -95,14 +103,16 @@ async fn process_command(opts: cli::Btfm, db_pool: Pool<Sqlite>) -> Result<(), E
                     tokio::time::sleep(std::time::Duration::from_secs(5)).await;
                     std::process::exit(1);
                 });
-                web_transcriber
+                (web_transcriber, phrase_index)
             };
             let discord_span = tracing::info_span!("discord");
             let discord_client_handle =
                 tokio::spawn(async move { client.start().await }.instrument(discord_span));
 
+            tokio::spawn(btfm::trash::purge_task(db_pool.clone()));
+
             let http_api = opts.config.http_api.clone();
-            let router = btfm::web::create_router(&http_api, db_pool, transcriber);
+            let router = btfm::web::create_router(&http_api, db_pool, transcriber, phrase_index);
             let http_span = tracing::info_span!("http_server");
             let server_handle = match (http_api.tls_certificate, http_api.tls_key) {
                 (None, None) => {
@@ -153,7 +163,7 @@ async fn process_command(opts: cli::Btfm, db_pool: Pool<Sqlite>) -> Result<(), E
                 if !file.exists() {
                     println!("{clip}");
                     if clean {
-                        db::remove_clip(&mut conn, clip.uuid.clone()).await?;
+                        db::purge_clip(&mut conn, clip.uuid.clone()).await?;
                     }
                 }
             }
@@ -180,15 +190,44 @@ async fn process_command(opts: cli::Btfm, db_pool: Pool<Sqlite>) -> Result<(), E
 
             Ok(())
         }
+        cli::Command::Retranscribe { skip_edited } => {
+            gstreamer::init()?;
+            let transcriber = Transcriber::new(&opts.config)?;
+            let job = RetranscribeJob::default();
+            job.start();
+
+            let task_job = job.clone();
+            let task_transcriber = transcriber.clone();
+            let task = tokio::spawn(async move {
+                retranscribe(&db_pool, &task_transcriber, skip_edited, &task_job).await
+            });
+            while !task.is_finished() {
+                tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                let status = job.status();
+                let done = status.transcribed + status.skipped + status.failed;
+                println!("Processed {done} of {} clips", status.total);
+            }
+            task.await??;
+            transcriber.shutdown().await;
+
+            let status = job.status();
+            println!(
+                "Re-transcribed {} clips, skipped {}, and failed on {}",
+                status.transcribed, status.skipped, status.failed
+            );
+            println!("Restart any running servers so they pick up the new speech.");
+            Ok(())
+        }
         cli::Command::Web {} => {
             gstreamer::init()?;
 
             let http_handle = axum_server::Handle::new();
-            let transcriber = {
+            let (transcriber, phrase_index) = {
                 let handle = http_handle.clone();
                 let btfm_data = BtfmData::new().await;
                 let transcriber = btfm_data.transcriber.clone();
                 let web_transcriber = btfm_data.transcriber;
+                let phrase_index = btfm_data.phrase_index;
 
                 tokio::spawn(async move {
                     let _shutdown_signal = tokio::signal::ctrl_c().await;
@@ -199,10 +238,12 @@ async fn process_command(opts: cli::Btfm, db_pool: Pool<Sqlite>) -> Result<(), E
                     tokio::time::sleep(std::time::Duration::from_secs(5)).await;
                     std::process::exit(1);
                 });
-                web_transcriber
+                (web_transcriber, phrase_index)
             };
+            tokio::spawn(btfm::trash::purge_task(db_pool.clone()));
+
             let http_api = opts.config.http_api.clone();
-            let router = btfm::web::create_router(&http_api, db_pool, transcriber);
+            let router = btfm::web::create_router(&http_api, db_pool, transcriber, phrase_index);
             match (http_api.tls_certificate, http_api.tls_key) {
                 (None, None) => {
                     info!("Starting HTTP server on {:?}", &http_api.url);
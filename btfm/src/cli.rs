@@ -39,6 +39,12 @@ pub enum Command {
         #[arg(long)]
         clean: bool,
     },
+    /// Run speech-to-text on every clip again, e.g. after changing the Whisper model
+    Retranscribe {
+        /// Leave clips whose detected speech was corrected by hand alone
+        #[arg(long)]
+        skip_edited: bool,
+    },
     /// Run the bot service
     Discord {},
     Web {},
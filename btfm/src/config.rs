@@ -33,6 +33,22 @@ pub struct Config {
     pub http_api: HttpApi,
     /// The time between random clip plays, in seconds.
     pub random_clip_interval: u64,
+    /// The minimum time, in seconds, before a clip can be played again; 0 disables the
+    /// cooldown. Individual clips can override this. It can be at most a year.
+    #[serde(default)]
+    pub clip_cooldown: u64,
+    /// How long, in days, removed clips are kept in the trash before they're purged.
+    #[serde(default = "default_trash_purge_days")]
+    pub trash_purge_days: u64,
+    /// Phrase matching configuration options
+    #[serde(default)]
+    pub matching: Matching,
+    /// Clip tag configuration options
+    #[serde(default)]
+    pub tags: Tags,
+    /// The timezone clip availability windows are in, e.g. "America/New_York"; defaults to UTC.
+    #[serde(default)]
+    pub timezone: chrono_tz::Tz,
 
     pub mimic_endpoint: Option<Url>,
 }
@@ -47,6 +63,10 @@ impl Config {
     }
 }
 
+fn default_trash_purge_days() -> u64 {
+    30
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Whisper {
     /// Path to the Whisper model. If file doesn't exist,
@@ -63,6 +83,55 @@ impl Default for Whisper {
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Matching {
+    /// Match phrases that are close to, but not exactly, what was transcribed.
+    pub fuzzy: bool,
+    /// When fuzzy matching, treat words that sound alike as near-identical.
+    pub phonetic: bool,
+    /// The minimum similarity, from 0 to 1, for a fuzzy match to count. Individual
+    /// phrases can override this.
+    pub similarity_threshold: f64,
+    /// The minimum number of words a clip's detected speech must have before it triggers the
+    /// clip. Short transcriptions are often speech-to-text hallucinations.
+    pub speech_detected_min_words: usize,
+}
+
+impl Default for Matching {
+    fn default() -> Self {
+        Matching {
+            fuzzy: false,
+            phonetic: true,
+            similarity_threshold: 0.8,
+            speech_detected_min_words: 3,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Tags {
+    /// If set, only clips with at least one of these tags are played.
+    pub active: Vec<String>,
+    /// Clips with any of these tags are never played.
+    pub excluded: Vec<String>,
+}
+
+impl Tags {
+    /// Whether a clip with the given tags is allowed to play; tags are compared without
+    /// regard to case.
+    pub fn allows(&self, tags: &[String]) -> bool {
+        let has_any = |names: &[String]| {
+            names.iter().any(|name| {
+                tags.iter()
+                    .any(|tag| tag.to_lowercase() == name.to_lowercase())
+            })
+        };
+        (self.active.is_empty() || has_any(&self.active)) && !has_any(&self.excluded)
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct HttpApi {
     /// The URL of an HTTP API used to manage the bot.
@@ -101,6 +170,11 @@ impl Default for Config {
             whisper: Default::default(),
             http_api: Default::default(),
             random_clip_interval: 60 * 15,
+            clip_cooldown: 0,
+            trash_purge_days: default_trash_purge_days(),
+            matching: Default::default(),
+            tags: Default::default(),
+            timezone: Default::default(),
             mimic_endpoint: None,
         }
     }
@@ -123,5 +197,11 @@ pub fn load_config(path: &str) -> Result<Config, Error> {
     let config: Config = toml::from_str(&config_string).inspect_err(|_| {
         println!("Example config format:\n\n{}", Config::default());
     })?;
+    if config.clip_cooldown > crate::db::MAX_COOLDOWN {
+        return Err(Error::ConfigValueError(format!(
+            "clip_cooldown can be at most {} seconds",
+            crate::db::MAX_COOLDOWN
+        )));
+    }
     Ok(config)
 }
@@ -0,0 +1,98 @@
+// SPDX-License-Identifier: GPL-2.0-or-later
+//
+// Provides functions for the windows of time when clips can play
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use btfm_api_structs::AvailabilityWindow;
+use sqlx::SqliteConnection;
+use tracing::instrument;
+
+/// Find the availability windows of each of the given clips in a single query.
+///
+/// # Returns
+///
+/// A map from each clip's primary key to its windows; clips without windows, which can always
+/// play, are left out.
+#[instrument(skip(connection))]
+pub async fn availability_for_clips(
+    connection: &mut SqliteConnection,
+    clip_uuids: &[String],
+) -> Result<HashMap<String, Vec<AvailabilityWindow>>, crate::Error> {
+    let clip_uuids = serde_json::to_string(clip_uuids)?;
+    let rows = sqlx::query!(
+        r#"
+        SELECT clip, start_date, end_date, weekdays, start_hour as "start_hour: u32", end_hour as "end_hour: u32"
+        FROM clip_availability
+        WHERE clip IN (SELECT value FROM json_each($1))
+        ORDER BY rowid
+        "#,
+        clip_uuids,
+    )
+    .fetch_all(&mut *connection)
+    .await?;
+
+    let mut availability: HashMap<String, Vec<AvailabilityWindow>> = HashMap::new();
+    for row in rows {
+        let weekdays = row
+            .weekdays
+            .split(',')
+            .filter_map(|day| chrono::Weekday::from_str(day).ok())
+            .collect();
+        availability
+            .entry(row.clip)
+            .or_default()
+            .push(AvailabilityWindow {
+                start_date: row.start_date,
+                end_date: row.end_date,
+                weekdays,
+                start_hour: row.start_hour,
+                end_hour: row.end_hour,
+            });
+    }
+    Ok(availability)
+}
+
+/// Replace a clip's availability windows; with no windows, the clip can always play.
+///
+/// Windows are expected to have been checked with [`crate::availability::is_valid`].
+#[instrument(skip(connection))]
+pub async fn set_availability(
+    connection: &mut SqliteConnection,
+    clip: &str,
+    windows: &[AvailabilityWindow],
+) -> Result<(), crate::Error> {
+    sqlx::query!(
+        r#"
+        DELETE FROM clip_availability
+        WHERE clip = $1
+        "#,
+        clip,
+    )
+    .execute(&mut *connection)
+    .await?;
+
+    for window in windows {
+        let weekdays = window
+            .weekdays
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+        sqlx::query!(
+            r#"
+            INSERT INTO clip_availability (clip, start_date, end_date, weekdays, start_hour, end_hour)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+            clip,
+            window.start_date,
+            window.end_date,
+            weekdays,
+            window.start_hour,
+            window.end_hour,
+        )
+        .execute(&mut *connection)
+        .await?;
+    }
+    Ok(())
+}
@@ -1,20 +1,30 @@
+use std::collections::{HashMap, HashSet};
 use std::fs;
 
 use btfm_api_structs::Clip as ApiClip;
-use btfm_api_structs::ClipUpload;
+use btfm_api_structs::{ClipPatch, ClipSort, ClipUpload, SortOrder};
 use chrono::NaiveDateTime;
 use rand::{distributions::Alphanumeric, prelude::*};
-use regex::Regex;
 use sqlx::{types::Uuid, SqliteConnection};
 use tracing::{error, info, instrument};
 
+use crate::availability;
+use crate::matching::{self, PhraseIndex};
 use crate::transcribe::Transcriber;
 
+/// The directory, relative to the data directory, clip audio is stored in.
+const CLIP_DIR: &str = "clips";
+/// The directory, relative to the data directory, audio for clips in the trash is stored in.
+const TRASH_DIR: &str = "trash";
+
+/// The longest cooldown, in seconds, a clip or the configuration can have.
+pub const MAX_COOLDOWN: u64 = 365 * 24 * 60 * 60;
+
 /// Representation of an audio clip in the database.
 ///
 /// Administrators add these clips which are played when phrases associated with the clip match
 /// the output of semi-accurate speech-to-text.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Clip {
     /// The unique identifier for the clip and primary key for the table.
     pub uuid: String,
@@ -32,6 +42,19 @@ pub struct Clip {
     pub title: String,
     /// A description of the clip for human consumption.
     pub description: Option<String>,
+    /// How likely the clip is to be picked relative to other clips; 0 means it is only played
+    /// when triggered by a phrase.
+    pub weight: f64,
+    /// Overrides the configured time, in seconds, before the clip can be played again.
+    pub cooldown: Option<i64>,
+    /// Whether `speech_detected` was corrected by hand rather than set by speech-to-text.
+    pub speech_detected_edited: bool,
+    /// Whether the clip is triggered by its detected speech, in addition to its phrases.
+    pub match_speech_detected: bool,
+    /// Disabled clips are never played.
+    pub enabled: bool,
+    /// When the clip was moved to the trash, if it's in the trash.
+    pub deleted_on: Option<NaiveDateTime>,
 }
 
 impl std::fmt::Display for Clip {
@@ -48,6 +71,33 @@ impl std::fmt::Display for Clip {
     }
 }
 
+impl Clip {
+    /// Check whether the clip was played too recently to be played again.
+    ///
+    /// Clips that have never been played are never cooling down. Otherwise the clip's own
+    /// cooldown is used if it has one, and the `default` cooldown (in seconds) if not. A cooldown
+    /// too long to represent never runs out.
+    pub fn cooling_down(&self, default: u64, now: NaiveDateTime) -> bool {
+        let cooldown = self
+            .cooldown
+            .unwrap_or_else(|| default.try_into().unwrap_or(i64::MAX));
+        self.plays > 0
+            && chrono::Duration::try_seconds(cooldown)
+                .is_none_or(|cooldown| now - self.last_played < cooldown)
+    }
+
+    /// The value the clip is sorted by in [`clips_page`], formatted as it's stored in the
+    /// database.
+    pub fn sort_key(&self, sort: ClipSort) -> String {
+        match sort {
+            ClipSort::Plays => self.plays.to_string(),
+            ClipSort::CreatedOn => self.created_on.format("%F %T%.f").to_string(),
+            ClipSort::LastPlayed => self.last_played.format("%F %T%.f").to_string(),
+            ClipSort::Title => self.title.clone(),
+        }
+    }
+}
+
 impl From<Clip> for ApiClip {
     fn from(clip: Clip) -> Self {
         Self {
@@ -60,6 +110,14 @@ impl From<Clip> for ApiClip {
             original_file_name: clip.original_file_name,
             description: clip.description.unwrap_or_default(),
             audio_file: clip.audio_file,
+            weight: clip.weight,
+            cooldown: clip.cooldown,
+            speech_detected_edited: clip.speech_detected_edited,
+            match_speech_detected: clip.match_speech_detected,
+            enabled: clip.enabled,
+            deleted_on: clip.deleted_on,
+            tags: vec![],
+            availability: vec![],
             phrases: None,
         }
     }
@@ -129,54 +187,193 @@ pub async fn last_play_time(connection: &mut SqliteConnection) -> NaiveDateTime
     }
 }
 
+/// A clip that matched some text, along with how well it matched.
+#[derive(Debug)]
+pub struct ClipMatch {
+    /// The clip that matched.
+    pub clip: Clip,
+    /// The phrase, or the clip's detected speech, that matched the text.
+    pub phrase: String,
+    /// How closely the phrase matched, from 0 to 1 where 1 is an exact match.
+    pub score: f64,
+    /// Whether the clip was played too recently to be played again.
+    pub cooling_down: bool,
+    /// The clip's exclusion phrase that was found in the text, if any.
+    pub excluded_by: Option<String>,
+    /// Whether the clip's tags keep it from playing under the configured tag rules.
+    pub muted: bool,
+    /// Whether the clip is outside all of its availability windows.
+    pub unavailable: bool,
+}
+
 /// Find all clips that match the given phrase.
 ///
 /// # Arguments
 ///
 /// `pool` - The SQLx database pool to use when issuing the query.
 ///
-/// `phrase` - Arbitrary text to search for matching phrases.
+/// `index` - The index of phrases to match against.
+///
+/// `phrase` - Arbitrary text to search for matching phrases, as it was transcribed. It's
+///            normalized before literal phrases and exclusions are matched against it, while
+///            regular expressions are matched against it as-is.
+///
+/// `speaker` - The Discord user ID of whoever said the text, if it's known.
 ///
 /// # Returns
 ///
-/// Clips that match the given phrase, if any. Clips match the phrase
-/// if they contain (according to deepspeech) the given phrase, or if a
-/// user-provided phrase is associated with it.
+/// Clips that match the given phrase, if any, along with the score of the match. Clips match
+/// the phrase if they contain (according to Whisper) the given phrase, or if a user-provided
+/// phrase is associated with it. Matches are made on whole words unless the phrase is in
+/// substring mode or is a regular expression. If fuzzy matching is enabled, literal phrases
+/// that are close enough also match. A phrase shared by several clips matches all of them, and
+/// each clip is returned at most once. Phrases restricted to other speakers never match.
+/// Asking for a "random" clip matches every clip that can be picked at random, which excludes
+/// clips with a weight of 0. Disabled clips never match, and clips that were played
+/// within their cooldown, that have an exclusion phrase in the text, that are muted by their
+/// tags, or that are outside their availability windows are skipped.
 #[instrument(skip_all)]
 pub async fn match_phrase(
     connection: &mut SqliteConnection,
+    index: &PhraseIndex,
     phrase: &str,
-) -> Result<Vec<Clip>, crate::Error> {
-    let clips = clips_list(connection).await?;
-    let phrases = super::list_phrases(connection).await?;
+    speaker: Option<u64>,
+) -> Result<Vec<ClipMatch>, crate::Error> {
+    Ok(find_matches(connection, index, phrase, speaker)
+        .await?
+        .into_iter()
+        .filter(|clip_match| {
+            if clip_match.cooling_down {
+                info!(
+                    "Skipping clip {} since it was played recently",
+                    &clip_match.clip.uuid
+                );
+            } else if let Some(exclusion) = &clip_match.excluded_by {
+                info!(
+                    "Skipping clip {} since the text contains '{}'",
+                    &clip_match.clip.uuid, exclusion
+                );
+            } else if clip_match.muted {
+                info!(
+                    "Skipping clip {} since its tags are muted",
+                    &clip_match.clip.uuid
+                );
+            } else if clip_match.unavailable {
+                info!(
+                    "Skipping clip {} since it's outside its availability windows",
+                    &clip_match.clip.uuid
+                );
+            } else {
+                info!(
+                    "Matched on '{}' (score {:.2})",
+                    &clip_match.phrase, clip_match.score
+                );
+            }
+            !clip_match.cooling_down
+                && clip_match.excluded_by.is_none()
+                && !clip_match.muted
+                && !clip_match.unavailable
+        })
+        .collect())
+}
+
+/// Find all clips that match the given phrase, including those that are cooling down, excluded,
+/// muted, or unavailable.
+///
+/// See [`match_phrase`] for details.
+#[instrument(skip_all)]
+pub async fn find_matches(
+    connection: &mut SqliteConnection,
+    index: &PhraseIndex,
+    phrase: &str,
+    speaker: Option<u64>,
+) -> Result<Vec<ClipMatch>, crate::Error> {
+    let config = crate::CONFIG.get().expect("Initialize the config");
+    let now = chrono::Utc::now().naive_utc();
+    let local_now = availability::local_now();
+    let text = crate::normalize::normalize(phrase);
 
     // And As It Is Such, So Also As Such Is It Unto You
-    if phrase.contains("random") {
-        return Ok(clips);
+    if matching::contains_words("random", &text) {
+        let clips = clips_list(connection)
+            .await?
+            .into_iter()
+            .filter(|clip| clip.enabled && clip.weight > 0.0)
+            .collect::<Vec<_>>();
+        let uuids = clips
+            .iter()
+            .map(|clip| clip.uuid.clone())
+            .collect::<Vec<_>>();
+        let tags = super::tags_for_clips(connection, &uuids).await?;
+        let windows = super::availability_for_clips(connection, &uuids).await?;
+        return Ok(clips
+            .into_iter()
+            .map(|clip| ClipMatch {
+                cooling_down: clip.cooling_down(config.clip_cooldown, now),
+                excluded_by: index.excluded_by(&clip.uuid, &text),
+                muted: is_muted(&tags, &clip.uuid),
+                unavailable: !availability::is_available(&windows, &clip.uuid, local_now),
+                clip,
+                phrase: "random".to_string(),
+                score: 1.0,
+            })
+            .collect());
     }
 
-    let mut matching_clips = Vec::new();
-    for clip in clips {
-        if let Some(speech_detected) = &clip.speech_detected {
-            if phrase.contains(speech_detected)
-                && speech_detected.split_whitespace().count() > 2_usize
-            {
-                info!(
-                    "Matched on '{}' based on the in-clip audio",
-                    &speech_detected
-                );
-                matching_clips.push(clip);
-            }
-        }
+    let candidates = index.find(phrase, speaker, &config.matching);
+    if candidates.is_empty() {
+        return Ok(vec![]);
     }
-    for potential_phrase in phrases {
-        if phrase.contains(&potential_phrase.phrase) {
-            let clip = get_clip(connection, potential_phrase.clip.clone()).await?;
-            matching_clips.push(clip);
-            info!("Matched on '{}'", &potential_phrase);
+    let uuids = candidates
+        .iter()
+        .map(|candidate| candidate.clip.clone())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    let clips = get_clips(connection, &uuids)
+        .await?
+        .into_iter()
+        .map(|clip| (clip.uuid.clone(), clip))
+        .collect::<HashMap<_, _>>();
+    let tags = super::tags_for_clips(connection, &uuids).await?;
+    let windows = super::availability_for_clips(connection, &uuids).await?;
+
+    // A clip can match on several phrases; only its best match is kept.
+    let mut matches: Vec<ClipMatch> = Vec::with_capacity(clips.len());
+    for candidate in candidates {
+        let Some(clip) = clips.get(&candidate.clip) else {
+            continue;
+        };
+        match matches.iter_mut().find(|m| m.clip.uuid == candidate.clip) {
+            Some(existing) if existing.score >= candidate.score => {}
+            Some(existing) => {
+                existing.phrase = candidate.phrase;
+                existing.score = candidate.score;
+            }
+            None => matches.push(ClipMatch {
+                clip: clip.clone(),
+                phrase: candidate.phrase,
+                score: candidate.score,
+                cooling_down: clip.cooling_down(config.clip_cooldown, now),
+                excluded_by: index.excluded_by(&clip.uuid, &text),
+                muted: is_muted(&tags, &clip.uuid),
+                unavailable: !availability::is_available(&windows, &clip.uuid, local_now),
+            }),
         }
     }
-    Ok(matching_clips)
+    Ok(matches)
+}
+
+/// Whether the clip's tags keep it from playing under the configured tag rules.
+///
+/// # Arguments
+///
+/// `tags` - The tag names of clips, as returned by [`super::tags_for_clips`].
+pub fn is_muted(tags: &HashMap<String, Vec<String>>, clip: &str) -> bool {
+    let config = crate::CONFIG.get().expect("Initialize the config");
+    !config
+        .tags
+        .allows(tags.get(clip).map(Vec::as_slice).unwrap_or_default())
 }
 
 #[instrument(skip(connection))]
@@ -187,9 +384,9 @@ pub async fn get_clip(
     Ok(sqlx::query_as!(
         Clip,
         r#"
-        SELECT uuid, created_on, last_played, plays, speech_detected, audio_file, original_file_name, title, description
+        SELECT uuid, created_on, last_played, plays, speech_detected, audio_file, original_file_name, title, description, weight, cooldown, speech_detected_edited, match_speech_detected, enabled, deleted_on
         FROM clips
-        WHERE clips.uuid = ?;
+        WHERE clips.uuid = ? AND deleted_on IS NULL;
         "#,
         uuid
     )
@@ -197,17 +394,37 @@ pub async fn get_clip(
     .await?)
 }
 
-/// Add a clip and any phrases included in the [`ClipUpload`] metadata.
-#[instrument(skip_all)]
-pub async fn add_clip(
+/// Get all the clips with the given primary keys in a single query.
+#[instrument(skip(connection))]
+pub async fn get_clips(
     connection: &mut SqliteConnection,
-    data: Vec<u8>,
-    metadata: ClipUpload,
-    filename: &str,
-    transcriber: Transcriber,
-) -> Result<Clip, crate::Error> {
+    uuids: &[String],
+) -> Result<Vec<Clip>, crate::Error> {
+    let uuids = serde_json::to_string(uuids)?;
+    Ok(sqlx::query_as!(
+        Clip,
+        r#"
+        SELECT *
+        FROM clips
+        WHERE clips.uuid IN (SELECT value FROM json_each(?)) AND deleted_on IS NULL;
+        "#,
+        uuids
+    )
+    .fetch_all(&mut *connection)
+    .await?)
+}
+
+/// Write clip audio to the data directory.
+///
+/// The file is written to the "clips/" directory with a random prefix so uploads with the same
+/// name don't collide.
+///
+/// # Returns
+///
+/// The path to the file relative to the data directory.
+fn write_audio_file(data: Vec<u8>, filename: &str) -> Result<String, crate::Error> {
     let config = crate::CONFIG.get().expect("Initialize the config");
-    let clip_dir = config.data_directory.join("clips/");
+    let clip_dir = config.data_directory.join(CLIP_DIR);
     if !clip_dir.exists() {
         std::fs::DirBuilder::new()
             .recursive(true)
@@ -219,33 +436,86 @@ pub async fn add_clip(
         .take(4)
         .map(char::from)
         .collect();
-    let prefixed_filename = format!("clips/{random_prefix}-{filename}");
+    let prefixed_filename = format!("{CLIP_DIR}/{random_prefix}-{filename}");
     let clip_destination = config.data_directory.join(&prefixed_filename);
     fs::write(&clip_destination, data)?;
-    let speech_detected = transcriber.file(clip_destination).await.await?;
-    let speech_detected = if speech_detected.trim().is_empty() {
+    Ok(prefixed_filename)
+}
+
+/// Run speech-to-text on an audio file, relative to the data directory.
+///
+/// # Returns
+///
+/// The normalized speech, or `None` if no speech was detected.
+async fn transcribe_audio_file(
+    audio_file: &str,
+    transcriber: Transcriber,
+) -> Result<Option<String>, crate::Error> {
+    let config = crate::CONFIG.get().expect("Initialize the config");
+    let speech_detected = transcriber
+        .file(config.data_directory.join(audio_file))
+        .await
+        .await?;
+    Ok(normalize_speech(&speech_detected))
+}
+
+/// Normalize speech so it can be used as a phrase.
+///
+/// # Returns
+///
+/// The normalized speech, or `None` if there isn't any.
+fn normalize_speech(speech: &str) -> Option<String> {
+    let speech = crate::normalize::normalize(speech);
+    if speech.is_empty() {
         None
     } else {
-        lazy_static::lazy_static! {
-            static ref RE: Regex = Regex::new(r"[^\w\s]").unwrap();
+        Some(speech)
+    }
+}
+
+/// Remove an audio file, relative to the data directory, logging rather than failing on errors.
+pub async fn remove_audio_file(audio_file: &str) {
+    let config = crate::CONFIG.get().expect("Initialize the config");
+    match tokio::fs::remove_file(config.data_directory.join(audio_file)).await {
+        Ok(_) => {
+            info!("Removed audio file {}", audio_file)
         }
-        let speech_detected = RE.replace_all(&speech_detected, "").trim().to_lowercase();
-        Some(speech_detected)
-    };
+        Err(err) => {
+            error!("Failed to remove audio file at {}: {}", audio_file, err)
+        }
+    }
+}
+
+/// Add a clip and any phrases included in the [`ClipUpload`] metadata.
+#[instrument(skip_all)]
+pub async fn add_clip(
+    connection: &mut SqliteConnection,
+    data: Vec<u8>,
+    metadata: ClipUpload,
+    filename: &str,
+    transcriber: Transcriber,
+) -> Result<Clip, crate::Error> {
+    let prefixed_filename = write_audio_file(data, filename)?;
+    let speech_detected = transcribe_audio_file(&prefixed_filename, transcriber).await?;
 
     let uuid = Uuid::new_v4().to_string();
+    let weight = metadata.weight.unwrap_or(1.0);
+    let match_speech_detected = metadata.match_speech_detected.unwrap_or(true);
     let clip = sqlx::query!(
         "
-        INSERT INTO clips (uuid, speech_detected, description, audio_file, title, original_file_name)
-        VALUES (?, ?, ?, ?, ?, ?)
-        RETURNING uuid, created_on, last_played, plays, speech_detected, description, audio_file, title, original_file_name
+        INSERT INTO clips (uuid, speech_detected, description, audio_file, title, original_file_name, weight, cooldown, match_speech_detected)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        RETURNING uuid, created_on, last_played, plays, speech_detected, description, audio_file, title, original_file_name, weight, cooldown, speech_detected_edited, match_speech_detected, enabled, deleted_on
         ",
         uuid,
         speech_detected,
         metadata.description,
         prefixed_filename,
         metadata.title,
-        filename
+        filename,
+        weight,
+        metadata.cooldown,
+        match_speech_detected
     )
     .fetch_one(&mut *connection)
     .await
@@ -259,115 +529,520 @@ pub async fn add_clip(
         audio_file: prefixed_filename,
         title: metadata.title,
         original_file_name: filename.to_string(),
+        weight: record.weight,
+        cooldown: record.cooldown,
+        speech_detected_edited: record.speech_detected_edited,
+        match_speech_detected: record.match_speech_detected,
+        enabled: record.enabled,
+        deleted_on: record.deleted_on,
     }).unwrap();
 
     for phrase in metadata.phrases.unwrap_or_default() {
-        super::add_phrase(&mut *connection, clip.uuid.clone(), &phrase).await?;
+        super::add_phrase(
+            &mut *connection,
+            std::slice::from_ref(&clip.uuid),
+            &phrase,
+            None,
+            false,
+            super::PhraseKind::Literal,
+            &super::Speakers::default(),
+        )
+        .await?;
     }
     Ok(clip)
 }
 
-/// Update a clip's metadata and phrases.
+/// Update a clip's metadata, phrases, and availability.
+///
+/// Only the fields set in the patch are changed. Phrases are unlinked from the clip before any
+/// are added, so a phrase that is both removed and added is replaced with a literal phrase.
+/// Phrases shared with other clips are left linked to those clips.
 ///
 /// # Arguments
 ///
 /// `uuid` - The primary key of the clip to update.
-/// `description` - The new human-readable description of the clip
-///
-/// # Returns
-///
-/// A Result with the number of affected rows when issuing the update.
+/// `patch` - The changes to make to the clip.
 #[instrument(skip(connection), fields(phrases_deleted, clip_updated, phrases_added))]
-pub async fn update_clip<S>(
+pub async fn update_clip(
     connection: &mut SqliteConnection,
     uuid: String,
-    description: &str,
-    phrases: &[S],
-) -> Result<(), crate::Error>
-where
-    S: AsRef<str> + std::fmt::Debug,
-{
+    patch: &ClipPatch,
+) -> Result<(), crate::Error> {
+    let set_cooldown = patch.cooldown.is_some();
+    let cooldown = patch.cooldown.flatten();
     let clip_updated = sqlx::query!(
         "
         UPDATE clips
-        SET description = $1
-        WHERE uuid = $2
+        SET title = COALESCE($1, title),
+            description = COALESCE($2, description),
+            weight = COALESCE($3, weight),
+            cooldown = CASE WHEN $4 THEN $5 ELSE cooldown END,
+            match_speech_detected = COALESCE($6, match_speech_detected),
+            enabled = COALESCE($7, enabled)
+        WHERE uuid = $8
         ",
-        description,
+        patch.title,
+        patch.description,
+        patch.weight,
+        set_cooldown,
+        cooldown,
+        patch.match_speech_detected,
+        patch.enabled,
         uuid,
     )
     .execute(&mut *connection)
     .await
     .map(|update| update.rows_affected())?;
     tracing::Span::current().record("clip_updated", clip_updated);
+    if clip_updated == 0 {
+        return Err(crate::Error::NotFound);
+    }
 
+    let remove_phrases = serde_json::to_string(
+        &patch
+            .remove_phrases
+            .iter()
+            .flat_map(|phrase| [crate::normalize::normalize(phrase), phrase.to_lowercase()])
+            .collect::<Vec<_>>(),
+    )?;
     let phrases_deleted = sqlx::query!(
         "
-        DELETE FROM clip_phrases
-        WHERE clip = $1
+        DELETE FROM clip_phrase_links
+        WHERE clip = $1 AND phrase IN (
+            SELECT uuid FROM phrases WHERE lower(phrase) IN (SELECT value FROM json_each($2))
+        )
         ",
         uuid,
+        remove_phrases,
     )
     .execute(&mut *connection)
     .await
     .map(|deleted| deleted.rows_affected())?;
     tracing::Span::current().record("phrases_deleted", phrases_deleted);
 
-    for phrase in phrases {
-        super::add_phrase(connection, uuid.clone(), phrase.as_ref()).await?;
+    let mut existing = super::phrases_for_clip(&mut *connection, uuid.clone())
+        .await?
+        .into_iter()
+        .map(|phrase| phrase.phrase)
+        .collect::<HashSet<_>>();
+    let mut phrases_added = 0;
+    for phrase in &patch.add_phrases {
+        if !existing.insert(crate::normalize::normalize(phrase)) {
+            continue;
+        }
+        super::add_phrase(
+            connection,
+            std::slice::from_ref(&uuid),
+            phrase,
+            None,
+            false,
+            super::PhraseKind::Literal,
+            &super::Speakers::default(),
+        )
+        .await?;
+        phrases_added += 1;
+    }
+    tracing::Span::current().record("phrases_added", phrases_added);
+
+    if let Some(availability) = &patch.availability {
+        super::set_availability(connection, &uuid, availability).await?;
+    }
+    if let Some(speech_detected) = &patch.speech_detected {
+        edit_speech_detected(connection, uuid, speech_detected).await?;
+    }
+
+    Ok(())
+}
+
+/// Make the given phrases the only ones that trigger a clip.
+///
+/// Phrases already linked to the clip that are in the list are left as they are, so they keep
+/// their settings and speakers. Phrases missing from the list are unlinked from the clip, but
+/// stay linked to any other clips, and new phrases are added as literal phrases.
+///
+/// # Arguments
+///
+/// `uuid` - The primary key of the clip.
+/// `phrases` - The text of every phrase that should trigger the clip; regular expressions are
+///             matched on their pattern.
+#[instrument(skip(connection), fields(phrases_deleted, phrases_added))]
+pub async fn replace_phrases(
+    connection: &mut SqliteConnection,
+    uuid: &str,
+    phrases: &[String],
+) -> Result<(), crate::Error> {
+    let is_listed = |phrase: &super::Phrase, text: &String| match phrase.kind {
+        super::PhraseKind::Literal => crate::normalize::normalize(text) == phrase.phrase,
+        super::PhraseKind::Regex => *text == phrase.phrase,
+    };
+    let (kept, removed): (Vec<_>, Vec<_>) = super::phrases_for_clip(&mut *connection, uuid.into())
+        .await?
+        .into_iter()
+        .partition(|phrase| phrases.iter().any(|text| is_listed(phrase, text)));
+
+    for phrase in &removed {
+        sqlx::query!(
+            "
+            DELETE FROM clip_phrase_links
+            WHERE clip = $1 AND phrase = $2
+            ",
+            uuid,
+            phrase.uuid,
+        )
+        .execute(&mut *connection)
+        .await?;
+    }
+    tracing::Span::current().record("phrases_deleted", removed.len());
+
+    let mut added = HashSet::new();
+    for text in phrases {
+        if kept.iter().any(|phrase| is_listed(phrase, text))
+            || !added.insert(crate::normalize::normalize(text))
+        {
+            continue;
+        }
+        super::add_phrase(
+            connection,
+            &[uuid.to_string()],
+            text,
+            None,
+            false,
+            super::PhraseKind::Literal,
+            &super::Speakers::default(),
+        )
+        .await?;
     }
-    tracing::Span::current().record("phrases_added", phrases.len());
+    tracing::Span::current().record("phrases_added", added.len());
 
     Ok(())
 }
 
-/// Remove a clip from the database and remove the audio file associated with it.
+/// A clip's new audio file, written to disk and transcribed.
+#[derive(Clone, Debug)]
+pub struct NewAudio {
+    /// Path to the audio file, relative to the data directory.
+    pub audio_file: String,
+    /// The name of the file as it was uploaded.
+    pub original_file_name: String,
+    /// The normalized speech, if any was detected.
+    pub speech_detected: Option<String>,
+}
+
+impl NewAudio {
+    /// Write the audio to the clips directory and run speech-to-text on it.
+    ///
+    /// This doesn't touch the database, so it can be done before starting a transaction. The
+    /// audio file is removed if speech-to-text fails.
+    #[instrument(skip(data, transcriber))]
+    pub async fn transcribe(
+        data: Vec<u8>,
+        filename: &str,
+        transcriber: Transcriber,
+    ) -> Result<Self, crate::Error> {
+        let audio_file = write_audio_file(data, filename)?;
+        match transcribe_audio_file(&audio_file, transcriber).await {
+            Ok(speech_detected) => Ok(NewAudio {
+                audio_file,
+                original_file_name: filename.to_string(),
+                speech_detected,
+            }),
+            Err(err) => {
+                remove_audio_file(&audio_file).await;
+                Err(err)
+            }
+        }
+    }
+}
+
+/// Replace a clip's audio and detected speech with audio from [`NewAudio::transcribe`].
+///
+/// Neither audio file is removed since the caller's transaction may not commit; once it does,
+/// remove the old one with [`remove_audio_file`], or the new one if it doesn't.
+///
+/// # Returns
+///
+/// The updated clip.
 #[instrument(skip(connection))]
-pub async fn remove_clip(
+pub async fn replace_audio(
     connection: &mut SqliteConnection,
     uuid: String,
+    audio: &NewAudio,
 ) -> Result<Clip, crate::Error> {
+    sqlx::query!(
+        "
+        UPDATE clips
+        SET audio_file = $1, original_file_name = $2, speech_detected = $3,
+            speech_detected_edited = FALSE
+        WHERE uuid = $4
+        ",
+        audio.audio_file,
+        audio.original_file_name,
+        audio.speech_detected,
+        uuid,
+    )
+    .execute(&mut *connection)
+    .await?;
+    get_clip(connection, uuid).await
+}
+
+/// Correct a clip's detected speech by hand.
+///
+/// The speech is normalized the same way as speech-to-text output, and the clip is marked as
+/// edited so bulk re-transcription can leave it alone.
+///
+/// # Returns
+///
+/// The normalized speech, or `None` if it was empty.
+#[instrument(skip(connection))]
+pub async fn edit_speech_detected(
+    connection: &mut SqliteConnection,
+    uuid: String,
+    speech_detected: &str,
+) -> Result<Option<String>, crate::Error> {
+    let speech_detected = normalize_speech(speech_detected);
+    sqlx::query!(
+        "
+        UPDATE clips
+        SET speech_detected = $1, speech_detected_edited = TRUE
+        WHERE uuid = $2
+        ",
+        speech_detected,
+        uuid,
+    )
+    .execute(&mut *connection)
+    .await?;
+    Ok(speech_detected)
+}
+
+/// Run speech-to-text on a clip's audio again and store the result.
+///
+/// This replaces any hand-corrected speech.
+///
+/// # Returns
+///
+/// The newly detected speech, if any.
+#[instrument(skip(connection, clip, transcriber), fields(clip = %clip.uuid))]
+pub async fn retranscribe_clip(
+    connection: &mut SqliteConnection,
+    clip: &Clip,
+    transcriber: Transcriber,
+) -> Result<Option<String>, crate::Error> {
+    let speech_detected = transcribe_audio_file(&clip.audio_file, transcriber).await?;
+    sqlx::query!(
+        "
+        UPDATE clips
+        SET speech_detected = $1, speech_detected_edited = FALSE
+        WHERE uuid = $2
+        ",
+        speech_detected,
+        clip.uuid,
+    )
+    .execute(&mut *connection)
+    .await?;
+    Ok(speech_detected)
+}
+
+/// Move a clip to the trash.
+///
+/// The clip is hidden everywhere except the trash until it's restored with [`restore_clip`] or
+/// removed for good with [`purge_clip`]. The clip is updated to keep its audio in the "trash/"
+/// directory, but the audio isn't moved since the caller's transaction may not commit; move it
+/// with [`AudioMove::apply`] once it does.
+///
+/// # Returns
+///
+/// The clip as it was before it was moved to the trash, and the move its audio needs.
+#[instrument(skip(connection))]
+pub async fn remove_clip(
+    connection: &mut SqliteConnection,
+    uuid: String,
+) -> Result<(Clip, AudioMove), crate::Error> {
+    let clip = get_clip(&mut *connection, uuid.clone()).await?;
+    let audio_move = AudioMove::new(&clip.audio_file, TRASH_DIR)?;
+    sqlx::query!(
+        "
+        UPDATE clips
+        SET deleted_on = CURRENT_TIMESTAMP, audio_file = $1
+        WHERE uuid = $2
+        ",
+        audio_move.to,
+        uuid,
+    )
+    .execute(&mut *connection)
+    .await?;
+    Ok((clip, audio_move))
+}
+
+/// List the clips in the trash, most recently removed first.
+#[instrument(skip_all)]
+pub async fn list_trash(connection: &mut SqliteConnection) -> Result<Vec<Clip>, crate::Error> {
+    Ok(sqlx::query_as!(
+        Clip,
+        r#"
+        SELECT *
+        FROM clips
+        WHERE deleted_on IS NOT NULL
+        ORDER BY deleted_on DESC
+        "#
+    )
+    .fetch_all(&mut *connection)
+    .await?)
+}
+
+/// Take a clip out of the trash.
+///
+/// Like [`remove_clip`], the clip is updated to keep its audio in the "clips/" directory, but
+/// moving the audio is left to the caller once its transaction commits.
+///
+/// # Returns
+///
+/// The clip as it was in the trash, and the move its audio needs.
+#[instrument(skip(connection))]
+pub async fn restore_clip(
+    connection: &mut SqliteConnection,
+    uuid: String,
+) -> Result<(Clip, AudioMove), crate::Error> {
     let clip = sqlx::query_as!(
         Clip,
         r#"
         SELECT *
         FROM clips
-        WHERE clips.uuid = ?;
+        WHERE uuid = $1 AND deleted_on IS NOT NULL
         "#,
-        uuid
+        uuid,
     )
-    .fetch_one(&mut *connection)
+    .fetch_optional(&mut *connection)
+    .await?
+    .ok_or(crate::Error::NotFound)?;
+    let audio_move = AudioMove::new(&clip.audio_file, CLIP_DIR)?;
+    sqlx::query!(
+        "
+        UPDATE clips
+        SET deleted_on = NULL, audio_file = $1
+        WHERE uuid = $2
+        ",
+        audio_move.to,
+        uuid,
+    )
+    .execute(&mut *connection)
     .await?;
+    Ok((clip, audio_move))
+}
 
-    let config = crate::CONFIG.get().expect("Initialize the config");
-    let clip_path = config.data_directory.join(&clip.audio_file);
-
+/// Undo [`remove_clip`] or [`restore_clip`] when the clip's audio couldn't be moved.
+///
+/// The clip is put back in or out of the trash with its old audio path, as `clip` has them; it
+/// should be the clip those functions returned.
+#[instrument(skip_all, fields(clip = %clip.uuid))]
+pub async fn revert_trash_change(
+    connection: &mut SqliteConnection,
+    clip: &Clip,
+) -> Result<(), crate::Error> {
     sqlx::query!(
         "
-        DELETE FROM clips
-        WHERE clips.uuid = ?;
+        UPDATE clips
+        SET deleted_on = $1, audio_file = $2
+        WHERE uuid = $3
         ",
-        uuid,
+        clip.deleted_on,
+        clip.audio_file,
+        clip.uuid,
     )
     .execute(&mut *connection)
     .await?;
+    Ok(())
+}
 
-    match tokio::fs::remove_file(&clip_path).await {
-        Ok(_) => {
-            info!("Removed audio file {}", &clip.audio_file)
-        }
-        Err(err) => {
-            error!(
-                "Failed to remove audio file at {}: {}",
-                &clip.audio_file, err
-            )
-        }
-    }
+/// Remove a clip from the database for good, along with its audio file and phrase links.
+///
+/// This works on clips whether or not they're in the trash.
+#[instrument(skip(connection))]
+pub async fn purge_clip(
+    connection: &mut SqliteConnection,
+    uuid: String,
+) -> Result<Clip, crate::Error> {
+    let clip = sqlx::query_as!(
+        Clip,
+        r#"
+        DELETE FROM clips
+        WHERE clips.uuid = ?
+        RETURNING *
+        "#,
+        uuid
+    )
+    .fetch_one(&mut *connection)
+    .await?;
+
+    remove_audio_file(&clip.audio_file).await;
 
     Ok(clip)
 }
 
-/// List all clips in the database. No pagination is performed. Good luck.
+/// Purge the clips that were moved to the trash before the given time.
+///
+/// # Returns
+///
+/// The purged clips.
+#[instrument(skip(connection))]
+pub async fn purge_trash(
+    connection: &mut SqliteConnection,
+    deleted_before: NaiveDateTime,
+) -> Result<Vec<Clip>, crate::Error> {
+    let clips = sqlx::query_as!(
+        Clip,
+        r#"
+        DELETE FROM clips
+        WHERE deleted_on < $1
+        RETURNING *
+        "#,
+        deleted_before,
+    )
+    .fetch_all(&mut *connection)
+    .await?;
+
+    for clip in clips.iter() {
+        remove_audio_file(&clip.audio_file).await;
+    }
+
+    Ok(clips)
+}
+
+/// An audio file that needs to be moved to go along with a change to its clip.
+#[derive(Clone, Debug)]
+pub struct AudioMove {
+    /// The file's current path, relative to the data directory.
+    pub from: String,
+    /// Where the file should be moved to, relative to the data directory.
+    pub to: String,
+}
+
+impl AudioMove {
+    /// Plan to move an audio file, relative to the data directory, into another directory.
+    fn new(audio_file: &str, directory: &str) -> Result<Self, crate::Error> {
+        let file_name = std::path::Path::new(audio_file)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or(crate::Error::NotFound)?;
+        Ok(AudioMove {
+            from: audio_file.to_string(),
+            to: format!("{directory}/{file_name}"),
+        })
+    }
+
+    /// Move the audio file.
+    pub async fn apply(&self) -> Result<(), crate::Error> {
+        let config = crate::CONFIG.get().expect("Initialize the config");
+        let destination = config.data_directory.join(&self.to);
+        if let Some(directory) = destination.parent() {
+            tokio::fs::create_dir_all(directory).await?;
+        }
+        tokio::fs::rename(config.data_directory.join(&self.from), destination).await?;
+        info!("Moved audio file {} to {}", self.from, self.to);
+        Ok(())
+    }
+}
+
+/// List all clips in the database; use [`clips_page`] to list them a page at a time.
 ///
 /// # Arguments
 ///
@@ -382,10 +1057,348 @@ pub async fn clips_list(connection: &mut SqliteConnection) -> Result<Vec<Clip>,
         Clip,
         r#"
         SELECT *
-        FROM clips;
+        FROM clips
+        WHERE deleted_on IS NULL;
         "#
     )
     .fetch_all(&mut *connection)
     .await
     .map_err(crate::Error::Database)
 }
+
+/// List a page of clips in the database.
+///
+/// # Arguments
+///
+/// `sort` - The field to sort clips by; ties are broken by the clip's primary key.
+///
+/// `order` - Whether to sort in ascending or descending order.
+///
+/// `enabled` - If provided, only list clips that are enabled or disabled.
+///
+/// `tag` - If provided, only list clips with this tag.
+///
+/// `after` - If provided, the primary key and [`Clip::sort_key`] of the last clip on the previous
+/// page; only clips that sort after it are listed. Clips added or removed between pages don't
+/// cause others to be skipped or repeated.
+///
+/// `limit` - The maximum number of clips to return.
+#[instrument(skip(connection))]
+pub async fn clips_page(
+    connection: &mut SqliteConnection,
+    sort: ClipSort,
+    order: SortOrder,
+    enabled: Option<bool>,
+    tag: Option<&str>,
+    after: Option<(&str, &str)>,
+    limit: i64,
+) -> Result<Vec<Clip>, crate::Error> {
+    let sort = match sort {
+        ClipSort::Plays => "plays",
+        ClipSort::CreatedOn => "created_on",
+        ClipSort::LastPlayed => "last_played",
+        ClipSort::Title => "title",
+    };
+    let descending = order == SortOrder::Desc;
+    let (after_uuid, after_key) = after.unzip();
+    sqlx::query_as!(
+        Clip,
+        r#"
+        SELECT
+            uuid as "uuid!", created_on as "created_on!", last_played as "last_played!",
+            plays as "plays!", speech_detected, audio_file as "audio_file!",
+            original_file_name as "original_file_name!", title as "title!", description,
+            weight as "weight!", cooldown, speech_detected_edited as "speech_detected_edited!",
+            match_speech_detected as "match_speech_detected!", enabled as "enabled!", deleted_on
+        FROM (
+            SELECT *,
+                CASE $1
+                    WHEN 'plays' THEN plays
+                    WHEN 'created_on' THEN created_on
+                    WHEN 'last_played' THEN last_played
+                    WHEN 'title' THEN title
+                END AS sort_key,
+                CASE $1 WHEN 'plays' THEN CAST($6 AS INTEGER) ELSE $6 END AS after_key
+            FROM clips
+            WHERE deleted_on IS NULL
+                AND ($3 IS NULL OR enabled = $3)
+                AND ($4 IS NULL OR uuid IN (
+                    SELECT clip_tags.clip
+                    FROM clip_tags
+                    JOIN tags ON tags.uuid = clip_tags.tag
+                    WHERE tags.name = lower($4)
+                ))
+        )
+        WHERE $5 IS NULL
+            OR CASE WHEN $2 THEN sort_key < after_key ELSE sort_key > after_key END
+            OR (sort_key = after_key AND uuid > $5)
+        ORDER BY
+            CASE WHEN $2 THEN NULL ELSE sort_key END ASC,
+            CASE WHEN $2 THEN sort_key END DESC,
+            uuid
+        LIMIT $7
+        "#,
+        sort,
+        descending,
+        enabled,
+        tag,
+        after_uuid,
+        after_key,
+        limit,
+    )
+    .fetch_all(&mut *connection)
+    .await
+    .map_err(crate::Error::Database)
+}
+
+/// A clip found by a full-text search.
+#[derive(Debug)]
+pub struct SearchMatch {
+    pub clip: Clip,
+    /// How well the clip matched; lower is better.
+    pub rank: f64,
+    /// An excerpt of the text that matched, with matching terms surrounded by `[` and `]`.
+    pub snippet: String,
+}
+
+/// Search clip titles, descriptions, detected speech, and phrases.
+///
+/// Each word in the query must appear in the clip, although words may be prefixes; "gen ken"
+/// matches "general kenobi".
+///
+/// # Returns
+///
+/// The best-matching clips, best first.
+#[instrument(skip(connection))]
+pub async fn search_clips(
+    connection: &mut SqliteConnection,
+    query: &str,
+    limit: i64,
+) -> Result<Vec<SearchMatch>, crate::Error> {
+    // Quote every term so the query can't contain FTS5 syntax.
+    let query = query
+        .split_whitespace()
+        .map(|term| format!("\"{}\"*", term.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if query.is_empty() {
+        return Ok(vec![]);
+    }
+    let records = sqlx::query!(
+        r#"
+        SELECT
+            clips.uuid as "uuid!", clips.created_on as "created_on!", clips.last_played as "last_played!",
+            clips.plays as "plays!", clips.speech_detected, clips.audio_file as "audio_file!",
+            clips.original_file_name as "original_file_name!", clips.title as "title!",
+            clips.description, clips.weight as "weight!", clips.cooldown,
+            clips.speech_detected_edited as "speech_detected_edited!",
+            clips.match_speech_detected as "match_speech_detected!",
+            clips.enabled as "enabled!",
+            clips.deleted_on,
+            bm25(clips_search) as "rank!: f64",
+            snippet(clips_search, -1, '[', ']', '…', 12) as "snippet!: String"
+        FROM clips_search
+        JOIN clips ON clips.uuid = clips_search.uuid
+        WHERE clips_search MATCH $1 AND clips.deleted_on IS NULL
+        ORDER BY bm25(clips_search)
+        LIMIT $2
+        "#,
+        query,
+        limit,
+    )
+    .fetch_all(&mut *connection)
+    .await?;
+    Ok(records
+        .into_iter()
+        .map(|record| SearchMatch {
+            clip: Clip {
+                uuid: record.uuid,
+                created_on: record.created_on,
+                last_played: record.last_played,
+                plays: record.plays,
+                speech_detected: record.speech_detected,
+                audio_file: record.audio_file,
+                original_file_name: record.original_file_name,
+                title: record.title,
+                description: record.description,
+                weight: record.weight,
+                cooldown: record.cooldown,
+                speech_detected_edited: record.speech_detected_edited,
+                match_speech_detected: record.match_speech_detected,
+                enabled: record.enabled,
+                deleted_on: record.deleted_on,
+            },
+            rank: record.rank,
+            snippet: record.snippet,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use sqlx::SqlitePool;
+
+    use super::*;
+
+    pub(in crate::db) async fn insert_clip(
+        connection: &mut SqliteConnection,
+        uuid: &str,
+        title: &str,
+    ) {
+        sqlx::query(
+            "INSERT INTO clips (uuid, audio_file, original_file_name, title) VALUES (?, ?, ?, ?)",
+        )
+        .bind(uuid)
+        .bind(format!("clips/{uuid}.ogg"))
+        .bind(format!("{title}.ogg"))
+        .bind(title)
+        .execute(connection)
+        .await
+        .unwrap();
+    }
+
+    async fn titles_after(
+        connection: &mut SqliteConnection,
+        order: SortOrder,
+        after: Option<&Clip>,
+    ) -> Vec<String> {
+        let after = after.map(|clip| (clip.uuid.clone(), clip.sort_key(ClipSort::Title)));
+        clips_page(
+            connection,
+            ClipSort::Title,
+            order,
+            None,
+            None,
+            after
+                .as_ref()
+                .map(|(uuid, key)| (uuid.as_str(), key.as_str())),
+            10,
+        )
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|clip| clip.title)
+        .collect()
+    }
+
+    #[sqlx::test]
+    async fn huge_cooldowns_never_run_out(pool: SqlitePool) {
+        let mut conn = pool.acquire().await.unwrap();
+        insert_clip(&mut conn, "1", "a").await;
+        let mut clip = get_clip(&mut conn, "1".to_string()).await.unwrap();
+        clip.plays = 1;
+        let later = clip.last_played + chrono::Duration::days(1);
+        assert!(!clip.cooling_down(60, later));
+        assert!(clip.cooling_down(u64::MAX, later));
+        clip.cooldown = Some(i64::MAX);
+        assert!(clip.cooling_down(0, later));
+    }
+
+    #[sqlx::test]
+    async fn replace_phrases_keeps_listed_phrases(pool: SqlitePool) {
+        let mut conn = pool.acquire().await.unwrap();
+        insert_clip(&mut conn, "1", "a").await;
+        insert_clip(&mut conn, "2", "b").await;
+        let regex = crate::db::add_phrase(
+            &mut conn,
+            &["1".to_string()],
+            r"\d+ apples",
+            Some(0.5),
+            true,
+            crate::db::PhraseKind::Regex,
+            &crate::db::Speakers {
+                allowed: vec![1],
+                denied: vec![],
+            },
+        )
+        .await
+        .unwrap();
+        let shared = crate::db::add_phrase(
+            &mut conn,
+            &["1".to_string(), "2".to_string()],
+            "nice",
+            None,
+            false,
+            crate::db::PhraseKind::Literal,
+            &crate::db::Speakers::default(),
+        )
+        .await
+        .unwrap();
+
+        replace_phrases(
+            &mut conn,
+            "1",
+            &[r"\d+ apples".to_string(), "Hello!".to_string()],
+        )
+        .await
+        .unwrap();
+
+        let mut phrases = crate::db::phrases_for_clip(&mut conn, "1".to_string())
+            .await
+            .unwrap();
+        phrases.sort_by(|a, b| a.phrase.cmp(&b.phrase));
+        assert_eq!(phrases.len(), 2);
+        assert_eq!(phrases[0].uuid, regex.uuid);
+        assert_eq!(phrases[0].kind, crate::db::PhraseKind::Regex);
+        assert!(phrases[0].substring);
+        assert_eq!(phrases[0].similarity_threshold, Some(0.5));
+        assert_eq!(phrases[0].speakers.allowed, [1]);
+        assert_eq!(phrases[1].phrase, "hello");
+        let shared = crate::db::get_phrase(&mut conn, shared.uuid).await.unwrap();
+        assert_eq!(shared.clips, ["2"]);
+    }
+
+    #[sqlx::test]
+    async fn reverting_a_restore_keeps_the_removal_time(pool: SqlitePool) {
+        let mut conn = pool.acquire().await.unwrap();
+        insert_clip(&mut conn, "1", "a").await;
+        remove_clip(&mut conn, "1".to_string()).await.unwrap();
+        sqlx::query("UPDATE clips SET deleted_on = '2020-01-01 00:00:00'")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+
+        let (trashed, audio_move) = restore_clip(&mut conn, "1".to_string()).await.unwrap();
+        assert_eq!(audio_move.from, "trash/1.ogg");
+        revert_trash_change(&mut conn, &trashed).await.unwrap();
+
+        let trash = list_trash(&mut conn).await.unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].deleted_on, trashed.deleted_on);
+        assert_eq!(trash[0].audio_file, "trash/1.ogg");
+    }
+
+    #[sqlx::test]
+    async fn clips_page_resumes_after_cursor(pool: SqlitePool) {
+        let mut conn = pool.acquire().await.unwrap();
+        insert_clip(&mut conn, "1", "b").await;
+        insert_clip(&mut conn, "2", "c").await;
+        insert_clip(&mut conn, "3", "c").await;
+        let first = clips_page(
+            &mut conn,
+            ClipSort::Title,
+            SortOrder::Asc,
+            None,
+            None,
+            None,
+            2,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            first.iter().map(|c| c.uuid.as_str()).collect::<Vec<_>>(),
+            ["1", "2"]
+        );
+
+        // Clips added before the cursor don't shift the next page.
+        insert_clip(&mut conn, "4", "a").await;
+        assert_eq!(
+            titles_after(&mut conn, SortOrder::Asc, first.last()).await,
+            ["c"]
+        );
+        assert_eq!(
+            titles_after(&mut conn, SortOrder::Desc, first.first()).await,
+            ["a"]
+        );
+    }
+}
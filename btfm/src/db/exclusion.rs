@@ -0,0 +1,109 @@
+// SPDX-License-Identifier: GPL-2.0-or-later
+//
+// Provides structures and functions for phrases that stop clips from playing
+use sqlx::{types::Uuid, SqliteConnection};
+use tracing::instrument;
+
+/// A phrase that vetoes a clip.
+///
+/// If the words of the phrase appear in a transcript, the clip isn't played even if one of its
+/// phrases matched. For example, a clip triggered by "nice" might be excluded by "not nice".
+#[derive(Clone, Debug)]
+pub struct Exclusion {
+    pub uuid: String,
+    /// The primary key of the clip the phrase vetoes.
+    pub clip: String,
+    pub phrase: String,
+}
+
+/// Add an exclusion phrase to a clip.
+#[instrument(skip(connection))]
+pub async fn add_exclusion(
+    connection: &mut SqliteConnection,
+    clip: String,
+    phrase: &str,
+) -> Result<Exclusion, crate::Error> {
+    let uuid = Uuid::new_v4().to_string();
+    let phrase = crate::normalize::normalize(phrase);
+    if phrase.is_empty() {
+        return Err(crate::Error::BadRequest);
+    }
+    Ok(sqlx::query_as!(
+        Exclusion,
+        r#"
+        INSERT INTO clip_exclusions (uuid, clip, phrase)
+        VALUES ($1, $2, $3)
+        RETURNING uuid, clip, phrase
+        "#,
+        uuid,
+        clip,
+        phrase,
+    )
+    .fetch_one(&mut *connection)
+    .await?)
+}
+
+/// Get a single exclusion by Uuid.
+#[instrument(skip(connection))]
+pub async fn get_exclusion(
+    connection: &mut SqliteConnection,
+    uuid: String,
+) -> Result<Exclusion, crate::Error> {
+    Ok(sqlx::query_as!(
+        Exclusion,
+        r#"
+        SELECT uuid, clip, phrase
+        FROM clip_exclusions
+        WHERE uuid = $1
+        "#,
+        uuid,
+    )
+    .fetch_one(&mut *connection)
+    .await?)
+}
+
+/// List exclusion phrases.
+///
+/// # Arguments
+///
+/// `clip` - If provided, only list the exclusions for this clip.
+#[instrument(skip(connection))]
+pub async fn list_exclusions(
+    connection: &mut SqliteConnection,
+    clip: Option<String>,
+) -> Result<Vec<Exclusion>, crate::Error> {
+    Ok(sqlx::query_as!(
+        Exclusion,
+        r#"
+        SELECT uuid, clip, phrase
+        FROM clip_exclusions
+        WHERE $1 IS NULL OR clip = $1
+        "#,
+        clip,
+    )
+    .fetch_all(&mut *connection)
+    .await?)
+}
+
+/// Remove an exclusion phrase.
+///
+/// # Returns
+///
+/// The exclusion that was deleted.
+#[instrument(skip(connection))]
+pub async fn remove_exclusion(
+    connection: &mut SqliteConnection,
+    uuid: String,
+) -> Result<Exclusion, crate::Error> {
+    Ok(sqlx::query_as!(
+        Exclusion,
+        r#"
+        DELETE FROM clip_exclusions
+        WHERE uuid = $1
+        RETURNING uuid, clip, phrase
+        "#,
+        uuid,
+    )
+    .fetch_one(&mut *connection)
+    .await?)
+}
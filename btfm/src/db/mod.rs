@@ -2,11 +2,31 @@
 //
 // Provides structures and functions for interacting with the database.
 
+mod availability;
 mod clip;
+mod exclusion;
+mod normalization;
 mod phrase;
+mod play;
+mod tag;
 
+pub use availability::{availability_for_clips, set_availability};
 pub use clip::{
-    add_clip, clips_list, get_clip, last_play_time, mark_played, match_phrase, remove_clip,
-    update_clip, Clip,
+    add_clip, clips_list, clips_page, edit_speech_detected, find_matches, get_clip, get_clips,
+    is_muted, last_play_time, list_trash, mark_played, match_phrase, purge_clip, purge_trash,
+    remove_audio_file, remove_clip, replace_audio, replace_phrases, restore_clip,
+    retranscribe_clip, revert_trash_change, search_clips, update_clip, AudioMove, Clip, ClipMatch,
+    NewAudio, SearchMatch, MAX_COOLDOWN,
+};
+pub use exclusion::{add_exclusion, get_exclusion, list_exclusions, remove_exclusion, Exclusion};
+pub use normalization::renormalize;
+pub use phrase::{
+    add_phrase, get_phrase, list_phrases, phrases_for_clip, phrases_for_clips, remove_phrase,
+    set_phrase_speakers, update_phrase, Phrase, PhraseKind, Speakers,
+};
+pub use play::{
+    list_plays, never_played, plays_per_day, record_play, top_clips, top_phrases, Play, Trigger,
+};
+pub use tag::{
+    add_tag, get_tag, list_tags, remove_tag, rename_tag, tag_clip, tags_for_clips, untag_clip, Tag,
 };
-pub use phrase::{add_phrase, get_phrase, list_phrases, phrases_for_clip, remove_phrase, Phrase};
@@ -0,0 +1,195 @@
+// SPDX-License-Identifier: GPL-2.0-or-later
+//
+// Provides functions to keep stored text in step with the normalization rules
+use sqlx::SqliteConnection;
+use tracing::{info, instrument};
+
+use super::phrase::{find_identical, get_phrase, merge_phrase};
+use crate::normalize::{normalize, VERSION};
+
+/// Re-normalize the text that's matched against transcripts if it was stored with older
+/// normalization rules.
+///
+/// Literal phrases, exclusions, and detected speech are rewritten; regular expression phrases
+/// are left alone. Phrases and exclusions that would normalize to nothing keep their old text,
+/// while detected speech that normalizes to nothing is cleared. A phrase that normalizes to the
+/// same text as an otherwise identical phrase is merged into it.
+///
+/// # Returns
+///
+/// The number of phrases, exclusions, and clips that were changed.
+#[instrument(skip(connection))]
+pub async fn renormalize(connection: &mut SqliteConnection) -> Result<u64, crate::Error> {
+    let version = sqlx::query_scalar!(
+        r#"
+        SELECT max(version) as "version!: i64"
+        FROM normalization
+        "#
+    )
+    .fetch_one(&mut *connection)
+    .await?;
+    if version >= VERSION {
+        return Ok(0);
+    }
+
+    let mut changed = 0;
+    let phrases = sqlx::query!(
+        r#"
+        SELECT uuid as "uuid!", phrase
+        FROM phrases
+        WHERE kind = 'literal'
+        "#
+    )
+    .fetch_all(&mut *connection)
+    .await?;
+    for row in phrases {
+        let phrase = normalize(&row.phrase);
+        if phrase.is_empty() || phrase == row.phrase {
+            continue;
+        }
+        let existing = get_phrase(connection, row.uuid.clone()).await?;
+        let identical = find_identical(
+            connection,
+            &phrase,
+            existing.similarity_threshold,
+            existing.substring,
+            existing.kind,
+            &existing.speakers,
+        )
+        .await?;
+        if let Some(identical) = identical {
+            merge_phrase(connection, &row.uuid, &identical).await?;
+            changed += 1;
+            continue;
+        }
+        sqlx::query!(
+            "
+            UPDATE phrases
+            SET phrase = $1
+            WHERE uuid = $2
+            ",
+            phrase,
+            row.uuid,
+        )
+        .execute(&mut *connection)
+        .await?;
+        changed += 1;
+    }
+
+    let exclusions = sqlx::query!(
+        r#"
+        SELECT uuid as "uuid!", phrase
+        FROM clip_exclusions
+        "#
+    )
+    .fetch_all(&mut *connection)
+    .await?;
+    for row in exclusions {
+        let phrase = normalize(&row.phrase);
+        if phrase.is_empty() || phrase == row.phrase {
+            continue;
+        }
+        sqlx::query!(
+            "
+            UPDATE clip_exclusions
+            SET phrase = $1
+            WHERE uuid = $2
+            ",
+            phrase,
+            row.uuid,
+        )
+        .execute(&mut *connection)
+        .await?;
+        changed += 1;
+    }
+
+    let clips = sqlx::query!(
+        r#"
+        SELECT uuid as "uuid!", speech_detected as "speech_detected!"
+        FROM clips
+        WHERE speech_detected IS NOT NULL
+        "#
+    )
+    .fetch_all(&mut *connection)
+    .await?;
+    for row in clips {
+        let speech_detected = Some(normalize(&row.speech_detected)).filter(|s| !s.is_empty());
+        if speech_detected.as_ref() == Some(&row.speech_detected) {
+            continue;
+        }
+        sqlx::query!(
+            "
+            UPDATE clips
+            SET speech_detected = $1
+            WHERE uuid = $2
+            ",
+            speech_detected,
+            row.uuid,
+        )
+        .execute(&mut *connection)
+        .await?;
+        changed += 1;
+    }
+
+    sqlx::query!(
+        "
+        UPDATE normalization
+        SET version = $1
+        ",
+        VERSION,
+    )
+    .execute(&mut *connection)
+    .await?;
+    info!(
+        from = version,
+        to = VERSION,
+        changed,
+        "Re-normalized phrases and detected speech"
+    );
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::SqlitePool;
+
+    use super::*;
+    use crate::db::clip::tests::insert_clip;
+
+    async fn insert_phrase(
+        connection: &mut SqliteConnection,
+        uuid: &str,
+        phrase: &str,
+        clip: &str,
+    ) {
+        sqlx::query("INSERT INTO phrases (uuid, phrase) VALUES (?, ?)")
+            .bind(uuid)
+            .bind(phrase)
+            .execute(&mut *connection)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO clip_phrase_links (clip, phrase) VALUES (?, ?)")
+            .bind(clip)
+            .bind(uuid)
+            .execute(&mut *connection)
+            .await
+            .unwrap();
+    }
+
+    #[sqlx::test]
+    async fn renormalize_merges_colliding_phrases(pool: SqlitePool) {
+        let mut conn = pool.acquire().await.unwrap();
+        insert_clip(&mut conn, "1", "contracted").await;
+        insert_clip(&mut conn, "2", "expanded").await;
+        insert_phrase(&mut conn, "a", "don't", "1").await;
+        insert_phrase(&mut conn, "b", "do not", "2").await;
+        renormalize(&mut conn).await.unwrap();
+
+        let phrases = crate::db::list_phrases(&mut conn).await.unwrap();
+        assert_eq!(phrases.len(), 1);
+        assert_eq!(phrases[0].uuid, "b");
+        let mut clips = phrases[0].clips.clone();
+        clips.sort();
+        assert_eq!(clips, ["1", "2"]);
+    }
+}
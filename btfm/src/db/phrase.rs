@@ -5,15 +5,137 @@ use serde::Serialize;
 use sqlx::{types::Uuid, SqliteConnection};
 use tracing::instrument;
 
+use crate::matching;
+
 /// Representation of a phrase in the database.
 ///
 /// Speech-to-text is run on incoming audio and the result is compared to these phrases.
-/// Phrases are associated with clips via `ClipPhrase` entries in a many-to-many relationship.
+/// Phrases are associated with clips via `clip_phrase_links` entries in a many-to-many
+/// relationship, so one phrase can trigger a pool of clips. A phrase is removed when it's no
+/// longer linked to any clips.
 #[derive(Clone, Debug, Serialize)]
 pub struct Phrase {
     pub uuid: String,
-    pub clip: String,
     pub phrase: String,
+    /// Overrides the configured minimum similarity for fuzzy matches of this phrase.
+    pub similarity_threshold: Option<f64>,
+    /// Match the phrase anywhere in the text, even inside other words.
+    pub substring: bool,
+    /// Whether the phrase is literal text or a regular expression.
+    pub kind: PhraseKind,
+    /// The primary keys of the clips the phrase triggers.
+    pub clips: Vec<String>,
+    /// The Discord users who can, or can't, trigger the phrase.
+    pub speakers: Speakers,
+}
+
+/// Restricts who can trigger a phrase.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Speakers {
+    /// The Discord user IDs allowed to trigger the phrase; if empty, anyone can.
+    pub allowed: Vec<u64>,
+    /// The Discord user IDs that can never trigger the phrase.
+    pub denied: Vec<u64>,
+}
+
+impl Speakers {
+    /// Whether the speaker can trigger the phrase; unknown speakers can only trigger phrases
+    /// that aren't restricted to particular users.
+    pub fn allows(&self, speaker: Option<u64>) -> bool {
+        match speaker {
+            Some(speaker) => {
+                (self.allowed.is_empty() || self.allowed.contains(&speaker))
+                    && !self.denied.contains(&speaker)
+            }
+            None => self.allowed.is_empty(),
+        }
+    }
+
+    /// Whether both allow and deny the same users, regardless of the order they're listed in.
+    pub fn same_as(&self, other: &Speakers) -> bool {
+        let sorted = |speakers: &[u64]| {
+            let mut speakers = speakers.to_vec();
+            speakers.sort_unstable();
+            speakers.dedup();
+            speakers
+        };
+        sorted(&self.allowed) == sorted(&other.allowed)
+            && sorted(&self.denied) == sorted(&other.denied)
+    }
+}
+
+/// A phrase as it's queried, with its clips and speakers separated by commas.
+struct PhraseRow {
+    uuid: String,
+    phrase: String,
+    similarity_threshold: Option<f64>,
+    substring: bool,
+    kind: PhraseKind,
+    clips: Option<String>,
+    allowed_speakers: Option<String>,
+    denied_speakers: Option<String>,
+}
+
+/// Parse a comma-separated list of user IDs.
+fn parse_speakers(speakers: Option<String>) -> Vec<u64> {
+    speakers
+        .map(|speakers| {
+            speakers
+                .split(',')
+                .filter_map(|speaker| speaker.parse().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+impl From<PhraseRow> for Phrase {
+    fn from(row: PhraseRow) -> Self {
+        Phrase {
+            uuid: row.uuid,
+            phrase: row.phrase,
+            similarity_threshold: row.similarity_threshold,
+            substring: row.substring,
+            kind: row.kind,
+            clips: row
+                .clips
+                .map(|clips| clips.split(',').map(String::from).collect())
+                .unwrap_or_default(),
+            speakers: Speakers {
+                allowed: parse_speakers(row.allowed_speakers),
+                denied: parse_speakers(row.denied_speakers),
+            },
+        }
+    }
+}
+
+/// The kinds of phrases that can trigger a clip.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum PhraseKind {
+    /// The phrase is matched as-is against speech.
+    #[default]
+    Literal,
+    /// The phrase is a regular expression matched against speech.
+    Regex,
+}
+
+impl From<btfm_api_structs::PhraseKind> for PhraseKind {
+    fn from(kind: btfm_api_structs::PhraseKind) -> Self {
+        match kind {
+            btfm_api_structs::PhraseKind::Literal => Self::Literal,
+            btfm_api_structs::PhraseKind::Regex => Self::Regex,
+        }
+    }
+}
+
+impl From<PhraseKind> for btfm_api_structs::PhraseKind {
+    fn from(kind: PhraseKind) -> Self {
+        match kind {
+            PhraseKind::Literal => Self::Literal,
+            PhraseKind::Regex => Self::Regex,
+        }
+    }
 }
 
 impl std::fmt::Display for Phrase {
@@ -39,18 +161,55 @@ pub async fn phrases_for_clip(
     connection: &mut SqliteConnection,
     clip_uuid: String,
 ) -> Result<Vec<Phrase>, crate::Error> {
-    sqlx::query_as!(
-        Phrase,
+    Ok(sqlx::query_as!(
+        PhraseRow,
         r#"
-        SELECT *
-        FROM clip_phrases
-        WHERE clip = $1
+        SELECT phrases.uuid as "uuid!", phrases.phrase as "phrase!", phrases.similarity_threshold, phrases.substring as "substring!", phrases.kind as "kind!: PhraseKind", group_concat(clip_phrase_links.clip) as "clips: String",
+            (SELECT group_concat(user_id) FROM phrase_speakers WHERE phrase = phrases.uuid AND allowed) as "allowed_speakers: String",
+            (SELECT group_concat(user_id) FROM phrase_speakers WHERE phrase = phrases.uuid AND NOT allowed) as "denied_speakers: String"
+        FROM phrases
+        JOIN clip_phrase_links ON clip_phrase_links.phrase = phrases.uuid
+        WHERE phrases.uuid IN (SELECT phrase FROM clip_phrase_links WHERE clip = $1)
+        GROUP BY phrases.uuid
         "#,
         clip_uuid
     )
     .fetch_all(&mut *connection)
     .await
-    .map_err(crate::Error::Database)
+    .map_err(crate::Error::Database)?
+    .into_iter()
+    .map(Phrase::from)
+    .collect())
+}
+
+/// Find the phrases associated with any of the given clips in a single query.
+#[instrument(skip(connection))]
+pub async fn phrases_for_clips(
+    connection: &mut SqliteConnection,
+    clip_uuids: &[String],
+) -> Result<Vec<Phrase>, crate::Error> {
+    let clip_uuids = serde_json::to_string(clip_uuids)?;
+    Ok(sqlx::query_as!(
+        PhraseRow,
+        r#"
+        SELECT phrases.uuid as "uuid!", phrases.phrase as "phrase!", phrases.similarity_threshold, phrases.substring as "substring!", phrases.kind as "kind!: PhraseKind", group_concat(clip_phrase_links.clip) as "clips: String",
+            (SELECT group_concat(user_id) FROM phrase_speakers WHERE phrase = phrases.uuid AND allowed) as "allowed_speakers: String",
+            (SELECT group_concat(user_id) FROM phrase_speakers WHERE phrase = phrases.uuid AND NOT allowed) as "denied_speakers: String"
+        FROM phrases
+        JOIN clip_phrase_links ON clip_phrase_links.phrase = phrases.uuid
+        WHERE phrases.uuid IN (
+            SELECT phrase FROM clip_phrase_links WHERE clip IN (SELECT value FROM json_each($1))
+        )
+        GROUP BY phrases.uuid
+        "#,
+        clip_uuids
+    )
+    .fetch_all(&mut *connection)
+    .await
+    .map_err(crate::Error::Database)?
+    .into_iter()
+    .map(Phrase::from)
+    .collect())
 }
 
 /// Get a single phrase by Uuid.
@@ -60,58 +219,318 @@ pub async fn get_phrase(
     phrase_uuid: String,
 ) -> Result<Phrase, crate::Error> {
     Ok(sqlx::query_as!(
-        Phrase,
+        PhraseRow,
         r#"
-        SELECT *
-        FROM clip_phrases
-        WHERE uuid = $1
+        SELECT phrases.uuid as "uuid!", phrases.phrase as "phrase!", phrases.similarity_threshold, phrases.substring as "substring!", phrases.kind as "kind!: PhraseKind", group_concat(clip_phrase_links.clip) as "clips: String",
+            (SELECT group_concat(user_id) FROM phrase_speakers WHERE phrase = phrases.uuid AND allowed) as "allowed_speakers: String",
+            (SELECT group_concat(user_id) FROM phrase_speakers WHERE phrase = phrases.uuid AND NOT allowed) as "denied_speakers: String"
+        FROM phrases
+        LEFT JOIN clip_phrase_links ON clip_phrase_links.phrase = phrases.uuid
+        WHERE phrases.uuid = $1
+        GROUP BY phrases.uuid
         "#,
         phrase_uuid,
     )
     .fetch_one(&mut *connection)
-    .await?)
+    .await?
+    .into())
 }
 
-/// Add a phrase to a clip.
+/// Add a phrase to some clips.
+///
+/// If an identical phrase already exists, including who can trigger it, the clips are linked to
+/// it rather than creating a duplicate phrase. The existing phrase is otherwise left as-is, since
+/// other clips may be linked to it.
+///
+/// # Arguments
+///
+/// `clips` - The primary keys of the clips to associate the phrase with; there must be at least
+///           one.
+/// `phrase` - The trigger phrase.
+/// `similarity_threshold` - The minimum similarity for a fuzzy match of this phrase; if `None`,
+///                          the configured default is used.
+/// `substring` - Match the phrase anywhere in the text rather than only on whole words.
+/// `kind` - Whether the phrase is literal text or a regular expression. Regular expressions
+///          are validated before being added.
+/// `speakers` - The users who can, or can't, trigger the phrase.
 #[instrument(skip(connection))]
 pub async fn add_phrase(
     connection: &mut SqliteConnection,
-    clip: String,
+    clips: &[String],
     phrase: &str,
+    similarity_threshold: Option<f64>,
+    substring: bool,
+    kind: PhraseKind,
+    speakers: &Speakers,
 ) -> Result<Phrase, crate::Error> {
-    let phrase = phrase.to_lowercase();
-    let uuid = Uuid::new_v4().to_string();
+    if clips.is_empty() {
+        return Err(crate::Error::BadRequest);
+    }
+    let phrase = prepare_phrase(phrase, kind)?;
+    let existing = find_identical(
+        connection,
+        &phrase,
+        similarity_threshold,
+        substring,
+        kind,
+        speakers,
+    )
+    .await?;
+
+    let uuid = match existing {
+        Some(uuid) => uuid,
+        None => {
+            let uuid = Uuid::new_v4().to_string();
+            sqlx::query!(
+                r#"
+                INSERT INTO phrases (uuid, phrase, similarity_threshold, substring, kind)
+                VALUES ($1, $2, $3, $4, $5)
+                "#,
+                uuid,
+                phrase,
+                similarity_threshold,
+                substring,
+                kind,
+            )
+            .execute(&mut *connection)
+            .await?;
+            if *speakers != Speakers::default() {
+                set_phrase_speakers(connection, uuid.clone(), speakers).await?;
+            }
+            uuid
+        }
+    };
+    link_clips(connection, &uuid, clips).await?;
+    get_phrase(connection, uuid).await
+}
+
+/// Find a phrase with the given text and settings that the given users can, or can't, trigger.
+///
+/// # Returns
+///
+/// The primary key of the phrase, if there is one.
+pub(super) async fn find_identical(
+    connection: &mut SqliteConnection,
+    phrase: &str,
+    similarity_threshold: Option<f64>,
+    substring: bool,
+    kind: PhraseKind,
+    speakers: &Speakers,
+) -> Result<Option<String>, crate::Error> {
+    let candidates = sqlx::query_scalar!(
+        r#"
+        SELECT uuid
+        FROM phrases
+        WHERE phrase = $1 AND similarity_threshold IS $2 AND substring = $3 AND kind = $4
+        "#,
+        phrase,
+        similarity_threshold,
+        substring,
+        kind,
+    )
+    .fetch_all(&mut *connection)
+    .await?;
+    for uuid in candidates {
+        if get_phrase(connection, uuid.clone())
+            .await?
+            .speakers
+            .same_as(speakers)
+        {
+            return Ok(Some(uuid));
+        }
+    }
+    Ok(None)
+}
+
+/// Link the clips of one phrase to another and remove the first phrase.
+pub(super) async fn merge_phrase(
+    connection: &mut SqliteConnection,
+    from: &str,
+    into: &str,
+) -> Result<(), crate::Error> {
     sqlx::query!(
         r#"
-        INSERT INTO clip_phrases (uuid, clip, phrase)
-        VALUES ($1, $2, $3)
+        INSERT OR IGNORE INTO clip_phrase_links (clip, phrase)
+        SELECT clip, $1 FROM clip_phrase_links WHERE phrase = $2
+        "#,
+        into,
+        from,
+    )
+    .execute(&mut *connection)
+    .await?;
+    sqlx::query!(
+        "
+        DELETE FROM phrases
+        WHERE uuid = $1
+        ",
+        from,
+    )
+    .execute(&mut *connection)
+    .await?;
+    Ok(())
+}
+
+/// Replace a phrase and the clips it's linked to.
+///
+/// The arguments are the same as for [`add_phrase`], although an identical phrase is not
+/// merged with this one.
+///
+/// # Returns
+///
+/// The updated phrase.
+#[instrument(skip(connection))]
+pub async fn update_phrase(
+    connection: &mut SqliteConnection,
+    uuid: String,
+    clips: &[String],
+    phrase: &str,
+    similarity_threshold: Option<f64>,
+    substring: bool,
+    kind: PhraseKind,
+) -> Result<Phrase, crate::Error> {
+    if clips.is_empty() {
+        return Err(crate::Error::BadRequest);
+    }
+    let phrase = prepare_phrase(phrase, kind)?;
+    sqlx::query!(
+        r#"
+        UPDATE phrases
+        SET phrase = $1, similarity_threshold = $2, substring = $3, kind = $4
+        WHERE uuid = $5
+        RETURNING uuid
         "#,
-        uuid,
-        clip,
         phrase,
+        similarity_threshold,
+        substring,
+        kind,
+        uuid,
+    )
+    .fetch_one(&mut *connection)
+    .await?;
+
+    // Link the new clips before unlinking the old ones so the phrase is never unlinked, which
+    // would remove it.
+    link_clips(connection, &uuid, clips).await?;
+    let clips = serde_json::to_string(clips)?;
+    sqlx::query!(
+        r#"
+        DELETE FROM clip_phrase_links
+        WHERE phrase = $1 AND clip NOT IN (SELECT value FROM json_each($2))
+        "#,
+        uuid,
+        clips,
     )
     .execute(&mut *connection)
     .await?;
 
-    Ok(Phrase { uuid, clip, phrase })
+    get_phrase(connection, uuid).await
+}
+
+/// Replace the users who can, or can't, trigger a phrase.
+///
+/// # Returns
+///
+/// The updated phrase.
+#[instrument(skip(connection))]
+pub async fn set_phrase_speakers(
+    connection: &mut SqliteConnection,
+    uuid: String,
+    speakers: &Speakers,
+) -> Result<Phrase, crate::Error> {
+    sqlx::query!(
+        r#"
+        DELETE FROM phrase_speakers
+        WHERE phrase = $1
+        "#,
+        uuid,
+    )
+    .execute(&mut *connection)
+    .await?;
+
+    let speakers = speakers
+        .allowed
+        .iter()
+        .map(|speaker| (speaker, true))
+        .chain(speakers.denied.iter().map(|speaker| (speaker, false)));
+    for (speaker, allowed) in speakers {
+        let speaker = i64::try_from(*speaker).map_err(|_| crate::Error::BadRequest)?;
+        sqlx::query!(
+            r#"
+            INSERT OR REPLACE INTO phrase_speakers (phrase, user_id, allowed)
+            VALUES ($1, $2, $3)
+            "#,
+            uuid,
+            speaker,
+            allowed,
+        )
+        .execute(&mut *connection)
+        .await?;
+    }
+
+    get_phrase(connection, uuid).await
+}
+
+async fn link_clips(
+    connection: &mut SqliteConnection,
+    phrase: &str,
+    clips: &[String],
+) -> Result<(), crate::Error> {
+    for clip in clips {
+        sqlx::query!(
+            r#"
+            INSERT OR IGNORE INTO clip_phrase_links (clip, phrase)
+            VALUES ($1, $2)
+            "#,
+            clip,
+            phrase,
+        )
+        .execute(&mut *connection)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Literal phrases are normalized like the speech they're matched against, and regular
+/// expressions must compile.
+fn prepare_phrase(phrase: &str, kind: PhraseKind) -> Result<String, crate::Error> {
+    match kind {
+        PhraseKind::Literal => {
+            let phrase = crate::normalize::normalize(phrase);
+            if phrase.is_empty() {
+                return Err(crate::Error::BadRequest);
+            }
+            Ok(phrase)
+        }
+        PhraseKind::Regex => {
+            matching::compile_regex(phrase)?;
+            Ok(phrase.to_string())
+        }
+    }
 }
 
 /// List all known phrases in the database.
 #[instrument(skip(connection))]
 pub async fn list_phrases(connection: &mut SqliteConnection) -> Result<Vec<Phrase>, crate::Error> {
-    sqlx::query_as!(
-        Phrase,
+    Ok(sqlx::query_as!(
+        PhraseRow,
         r#"
-        SELECT *
-        FROM clip_phrases;
+        SELECT phrases.uuid as "uuid!", phrases.phrase as "phrase!", phrases.similarity_threshold, phrases.substring as "substring!", phrases.kind as "kind!: PhraseKind", group_concat(clip_phrase_links.clip) as "clips: String",
+            (SELECT group_concat(user_id) FROM phrase_speakers WHERE phrase = phrases.uuid AND allowed) as "allowed_speakers: String",
+            (SELECT group_concat(user_id) FROM phrase_speakers WHERE phrase = phrases.uuid AND NOT allowed) as "denied_speakers: String"
+        FROM phrases
+        LEFT JOIN clip_phrase_links ON clip_phrase_links.phrase = phrases.uuid
+        GROUP BY phrases.uuid
         "#
     )
     .fetch_all(&mut *connection)
     .await
-    .map_err(crate::Error::Database)
+    .map_err(crate::Error::Database)?
+    .into_iter()
+    .map(Phrase::from)
+    .collect())
 }
 
-/// Remove the phrase.
+/// Remove the phrase from all its clips.
 ///
 /// # Returns
 ///
@@ -121,21 +540,11 @@ pub async fn remove_phrase(
     connection: &mut SqliteConnection,
     uuid: String,
 ) -> Result<Phrase, crate::Error> {
-    let phrase = sqlx::query_as!(
-        Phrase,
-        r#"
-        SELECT *
-        FROM clip_phrases
-        WHERE uuid = $1
-        "#,
-        uuid,
-    )
-    .fetch_one(&mut *connection)
-    .await?;
+    let phrase = get_phrase(&mut *connection, uuid.clone()).await?;
 
     sqlx::query!(
         "
-        DELETE FROM clip_phrases
+        DELETE FROM phrases
         WHERE uuid = $1
         ",
         uuid,
@@ -145,3 +554,71 @@ pub async fn remove_phrase(
 
     Ok(phrase)
 }
+
+#[cfg(test)]
+mod tests {
+    use sqlx::SqlitePool;
+
+    use super::*;
+    use crate::db::clip::tests::insert_clip;
+
+    #[sqlx::test]
+    async fn add_phrase_keeps_phrases_with_different_speakers_apart(pool: SqlitePool) {
+        let mut conn = pool.acquire().await.unwrap();
+        insert_clip(&mut conn, "1", "anyone").await;
+        insert_clip(&mut conn, "2", "restricted").await;
+        insert_clip(&mut conn, "3", "also restricted").await;
+        let restricted = Speakers {
+            allowed: vec![1, 2],
+            denied: vec![],
+        };
+
+        let open = add_phrase(
+            &mut conn,
+            &["1".to_string()],
+            "nice",
+            None,
+            false,
+            PhraseKind::Literal,
+            &Speakers::default(),
+        )
+        .await
+        .unwrap();
+        let first = add_phrase(
+            &mut conn,
+            &["2".to_string()],
+            "nice",
+            None,
+            false,
+            PhraseKind::Literal,
+            &restricted,
+        )
+        .await
+        .unwrap();
+        let second = add_phrase(
+            &mut conn,
+            &["3".to_string()],
+            "nice",
+            None,
+            false,
+            PhraseKind::Literal,
+            &Speakers {
+                allowed: vec![2, 1],
+                denied: vec![],
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_ne!(open.uuid, first.uuid);
+        let open = get_phrase(&mut conn, open.uuid).await.unwrap();
+        assert_eq!(open.clips, ["1"]);
+        assert_eq!(open.speakers, Speakers::default());
+        // The same speakers in a different order are still the same phrase.
+        assert_eq!(first.uuid, second.uuid);
+        let mut clips = second.clips;
+        clips.sort();
+        assert_eq!(clips, ["2", "3"]);
+        assert!(second.speakers.same_as(&restricted));
+    }
+}
@@ -0,0 +1,256 @@
+// SPDX-License-Identifier: GPL-2.0-or-later
+//
+// Provides structures and functions for the history of clips played
+use btfm_api_structs::{ClipPlays, DailyPlays, PhrasePlays, UnplayedClip};
+use chrono::{NaiveDate, NaiveDateTime};
+use sqlx::{types::Uuid, SqliteConnection};
+use tracing::instrument;
+
+/// A record of a clip being played.
+#[derive(Clone, Debug)]
+pub struct Play {
+    pub uuid: String,
+    /// The primary key of the clip that was played.
+    pub clip: String,
+    /// When the clip was played.
+    pub played_on: NaiveDateTime,
+    /// What caused the clip to be played.
+    pub triggered_by: Trigger,
+    /// The Discord user whose speech triggered the clip, if known.
+    pub user_id: Option<i64>,
+    /// The voice stream the triggering speech came from.
+    pub ssrc: Option<i64>,
+    /// What the bot heard, if the clip was triggered by speech.
+    pub transcript: Option<String>,
+    /// The phrase that matched the transcript.
+    pub phrase: Option<String>,
+}
+
+/// What caused a clip to be played.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, sqlx::Type)]
+#[sqlx(rename_all = "lowercase")]
+pub enum Trigger {
+    /// Someone said a phrase that matched the clip.
+    Phrase,
+    /// The clip was picked by the random clip player.
+    Random,
+    /// The clip was played via the HTTP API.
+    ///
+    /// The HTTP API has no way to play clips yet, since the voice connection belongs to the
+    /// Discord client, but plays started through it should be recorded with this.
+    Api,
+}
+
+impl From<Trigger> for btfm_api_structs::Trigger {
+    fn from(trigger: Trigger) -> Self {
+        match trigger {
+            Trigger::Phrase => Self::Phrase,
+            Trigger::Random => Self::Random,
+            Trigger::Api => Self::Api,
+        }
+    }
+}
+
+impl From<Play> for btfm_api_structs::Play {
+    fn from(play: Play) -> Self {
+        Self {
+            uuid: play.uuid,
+            clip: play.clip,
+            played_on: play.played_on,
+            triggered_by: play.triggered_by.into(),
+            user_id: play.user_id.and_then(|id| id.try_into().ok()),
+            ssrc: play.ssrc.and_then(|ssrc| ssrc.try_into().ok()),
+            transcript: play.transcript,
+            phrase: play.phrase,
+        }
+    }
+}
+
+/// Record that a clip was played.
+///
+/// # Arguments
+///
+/// `clip` - The primary key of the clip that was played.
+///
+/// `triggered_by` - What caused the clip to be played.
+///
+/// `user_id` - The Discord user whose speech triggered the clip, if any.
+///
+/// `ssrc` - The voice stream the triggering speech came from, if any.
+///
+/// `transcript` - What the bot heard, if anything.
+///
+/// `phrase` - The phrase that matched the transcript, if any.
+#[instrument(skip(connection, transcript))]
+pub async fn record_play(
+    connection: &mut SqliteConnection,
+    clip: &str,
+    triggered_by: Trigger,
+    user_id: Option<u64>,
+    ssrc: Option<u32>,
+    transcript: Option<&str>,
+    phrase: Option<&str>,
+) -> Result<Play, crate::Error> {
+    let uuid = Uuid::new_v4().to_string();
+    let user_id = user_id.and_then(|id| i64::try_from(id).ok());
+    let ssrc = ssrc.map(i64::from);
+    Ok(sqlx::query_as!(
+        Play,
+        r#"
+        INSERT INTO plays (uuid, clip, triggered_by, user_id, ssrc, transcript, phrase)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        RETURNING uuid, clip, played_on, triggered_by as "triggered_by: Trigger", user_id, ssrc, transcript, phrase
+        "#,
+        uuid,
+        clip,
+        triggered_by,
+        user_id,
+        ssrc,
+        transcript,
+        phrase,
+    )
+    .fetch_one(&mut *connection)
+    .await?)
+}
+
+/// List plays, most recent first.
+///
+/// # Arguments
+///
+/// `clip` - If provided, only list plays of this clip.
+///
+/// `limit` - The maximum number of plays to return.
+///
+/// `offset` - The number of plays to skip.
+///
+/// # Returns
+///
+/// A page of plays, along with the total number of plays that match.
+#[instrument(skip(connection))]
+pub async fn list_plays(
+    connection: &mut SqliteConnection,
+    clip: Option<String>,
+    limit: i64,
+    offset: i64,
+) -> Result<(Vec<Play>, i64), crate::Error> {
+    let plays = sqlx::query_as!(
+        Play,
+        r#"
+        SELECT uuid, clip, played_on, triggered_by as "triggered_by: Trigger", user_id, ssrc, transcript, phrase
+        FROM plays
+        WHERE $1 IS NULL OR clip = $1
+        ORDER BY played_on DESC, rowid DESC
+        LIMIT $2 OFFSET $3
+        "#,
+        clip,
+        limit,
+        offset,
+    )
+    .fetch_all(&mut *connection)
+    .await?;
+    let total = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) as "count!: i64"
+        FROM plays
+        WHERE $1 IS NULL OR clip = $1
+        "#,
+        clip,
+    )
+    .fetch_one(&mut *connection)
+    .await?;
+    Ok((plays, total))
+}
+
+/// Find the most-played clips between two times.
+#[instrument(skip(connection))]
+pub async fn top_clips(
+    connection: &mut SqliteConnection,
+    since: NaiveDateTime,
+    until: NaiveDateTime,
+    limit: i64,
+) -> Result<Vec<ClipPlays>, crate::Error> {
+    Ok(sqlx::query_as!(
+        ClipPlays,
+        r#"
+        SELECT clips.uuid as "uuid!", clips.title as "title!", COUNT(*) as "plays!: i64"
+        FROM plays
+        JOIN clips ON clips.uuid = plays.clip
+        WHERE plays.played_on >= $1 AND plays.played_on < $2
+        GROUP BY clips.uuid
+        ORDER BY 3 DESC, clips.title
+        LIMIT $3
+        "#,
+        since,
+        until,
+        limit,
+    )
+    .fetch_all(&mut *connection)
+    .await?)
+}
+
+/// Count the plays on each day between two times; days without plays are omitted.
+#[instrument(skip(connection))]
+pub async fn plays_per_day(
+    connection: &mut SqliteConnection,
+    since: NaiveDateTime,
+    until: NaiveDateTime,
+) -> Result<Vec<DailyPlays>, crate::Error> {
+    Ok(sqlx::query_as!(
+        DailyPlays,
+        r#"
+        SELECT date(played_on) as "day!: NaiveDate", COUNT(*) as "plays!: i64"
+        FROM plays
+        WHERE played_on >= $1 AND played_on < $2
+        GROUP BY 1
+        ORDER BY 1
+        "#,
+        since,
+        until,
+    )
+    .fetch_all(&mut *connection)
+    .await?)
+}
+
+/// Find the phrases that triggered the most plays between two times.
+#[instrument(skip(connection))]
+pub async fn top_phrases(
+    connection: &mut SqliteConnection,
+    since: NaiveDateTime,
+    until: NaiveDateTime,
+    limit: i64,
+) -> Result<Vec<PhrasePlays>, crate::Error> {
+    Ok(sqlx::query_as!(
+        PhrasePlays,
+        r#"
+        SELECT phrase as "phrase!", COUNT(*) as "plays!: i64"
+        FROM plays
+        WHERE phrase IS NOT NULL AND played_on >= $1 AND played_on < $2
+        GROUP BY phrase
+        ORDER BY 2 DESC, phrase
+        LIMIT $3
+        "#,
+        since,
+        until,
+        limit,
+    )
+    .fetch_all(&mut *connection)
+    .await?)
+}
+
+/// Find the clips that have never been played, oldest first.
+#[instrument(skip_all)]
+pub async fn never_played(
+    connection: &mut SqliteConnection,
+) -> Result<Vec<UnplayedClip>, crate::Error> {
+    Ok(sqlx::query_as!(
+        UnplayedClip,
+        r#"
+        SELECT uuid, title, created_on
+        FROM clips
+        WHERE plays = 0 AND deleted_on IS NULL
+        ORDER BY created_on
+        "#,
+    )
+    .fetch_all(&mut *connection)
+    .await?)
+}
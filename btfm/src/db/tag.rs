@@ -0,0 +1,275 @@
+// SPDX-License-Identifier: GPL-2.0-or-later
+//
+// Provides structures and functions for the tags clips are grouped by
+use std::collections::HashMap;
+
+use sqlx::{types::Uuid, SqliteConnection};
+use tracing::instrument;
+
+/// A label for a group of clips.
+///
+/// Tags can be configured to mute clips, or to only play clips with certain tags. Tag names
+/// are unique and lowercase.
+#[derive(Clone, Debug)]
+pub struct Tag {
+    pub uuid: String,
+    pub name: String,
+    /// The primary keys of the clips with the tag.
+    pub clips: Vec<String>,
+}
+
+/// A tag as it's queried, with its clips separated by commas.
+struct TagRow {
+    uuid: String,
+    name: String,
+    clips: Option<String>,
+}
+
+impl From<TagRow> for Tag {
+    fn from(row: TagRow) -> Self {
+        Tag {
+            uuid: row.uuid,
+            name: row.name,
+            clips: row
+                .clips
+                .map(|clips| clips.split(',').map(String::from).collect())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl std::fmt::Display for Tag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Tag ID {}: \"{}\"", self.uuid, self.name)
+    }
+}
+
+/// Tag names are trimmed and lowercase, and can't be empty.
+fn prepare_name(name: &str) -> Result<String, crate::Error> {
+    let name = name.trim().to_lowercase();
+    if name.is_empty() {
+        return Err(crate::Error::BadRequest);
+    }
+    Ok(name)
+}
+
+/// Get a single tag by Uuid.
+#[instrument(skip(connection))]
+pub async fn get_tag(connection: &mut SqliteConnection, uuid: String) -> Result<Tag, crate::Error> {
+    Ok(sqlx::query_as!(
+        TagRow,
+        r#"
+        SELECT tags.uuid as "uuid!", tags.name as "name!", group_concat(clip_tags.clip) as "clips: String"
+        FROM tags
+        LEFT JOIN clip_tags ON clip_tags.tag = tags.uuid
+        WHERE tags.uuid = $1
+        GROUP BY tags.uuid
+        "#,
+        uuid,
+    )
+    .fetch_one(&mut *connection)
+    .await?
+    .into())
+}
+
+/// List all tags, ordered by name.
+#[instrument(skip(connection))]
+pub async fn list_tags(connection: &mut SqliteConnection) -> Result<Vec<Tag>, crate::Error> {
+    Ok(sqlx::query_as!(
+        TagRow,
+        r#"
+        SELECT tags.uuid as "uuid!", tags.name as "name!", group_concat(clip_tags.clip) as "clips: String"
+        FROM tags
+        LEFT JOIN clip_tags ON clip_tags.tag = tags.uuid
+        GROUP BY tags.uuid
+        ORDER BY tags.name
+        "#
+    )
+    .fetch_all(&mut *connection)
+    .await?
+    .into_iter()
+    .map(Tag::from)
+    .collect())
+}
+
+/// Add a new tag.
+///
+/// # Returns
+///
+/// The new tag, or [`crate::Error::Conflict`] if a tag with the name already exists.
+#[instrument(skip(connection))]
+pub async fn add_tag(connection: &mut SqliteConnection, name: &str) -> Result<Tag, crate::Error> {
+    let name = prepare_name(name)?;
+    let uuid = Uuid::new_v4().to_string();
+    let inserted = sqlx::query!(
+        r#"
+        INSERT OR IGNORE INTO tags (uuid, name)
+        VALUES ($1, $2)
+        "#,
+        uuid,
+        name,
+    )
+    .execute(&mut *connection)
+    .await?;
+    if inserted.rows_affected() == 0 {
+        return Err(crate::Error::Conflict);
+    }
+    get_tag(connection, uuid).await
+}
+
+/// Rename a tag.
+///
+/// # Returns
+///
+/// The renamed tag, or [`crate::Error::Conflict`] if another tag has the name.
+#[instrument(skip(connection))]
+pub async fn rename_tag(
+    connection: &mut SqliteConnection,
+    uuid: String,
+    name: &str,
+) -> Result<Tag, crate::Error> {
+    let name = prepare_name(name)?;
+    let taken = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (SELECT 1 FROM tags WHERE name = $1 AND uuid != $2) as "taken!: bool"
+        "#,
+        name,
+        uuid,
+    )
+    .fetch_one(&mut *connection)
+    .await?;
+    if taken {
+        return Err(crate::Error::Conflict);
+    }
+    sqlx::query!(
+        r#"
+        UPDATE tags
+        SET name = $1
+        WHERE uuid = $2
+        RETURNING uuid
+        "#,
+        name,
+        uuid,
+    )
+    .fetch_one(&mut *connection)
+    .await?;
+    get_tag(connection, uuid).await
+}
+
+/// Remove a tag from all its clips and delete it.
+///
+/// # Returns
+///
+/// The tag that was deleted.
+#[instrument(skip(connection))]
+pub async fn remove_tag(
+    connection: &mut SqliteConnection,
+    uuid: String,
+) -> Result<Tag, crate::Error> {
+    let tag = get_tag(&mut *connection, uuid.clone()).await?;
+    sqlx::query!(
+        "
+        DELETE FROM tags
+        WHERE uuid = $1
+        ",
+        uuid,
+    )
+    .execute(&mut *connection)
+    .await?;
+    Ok(tag)
+}
+
+/// Tag a clip, creating the tag if it doesn't exist.
+///
+/// # Returns
+///
+/// The tag the clip was given.
+#[instrument(skip(connection))]
+pub async fn tag_clip(
+    connection: &mut SqliteConnection,
+    clip: String,
+    name: &str,
+) -> Result<Tag, crate::Error> {
+    let name = prepare_name(name)?;
+    let uuid = Uuid::new_v4().to_string();
+    let uuid = sqlx::query_scalar!(
+        r#"
+        INSERT INTO tags (uuid, name)
+        VALUES ($1, $2)
+        ON CONFLICT (name) DO UPDATE SET name = excluded.name
+        RETURNING uuid
+        "#,
+        uuid,
+        name,
+    )
+    .fetch_one(&mut *connection)
+    .await?;
+    sqlx::query!(
+        r#"
+        INSERT OR IGNORE INTO clip_tags (clip, tag)
+        VALUES ($1, $2)
+        "#,
+        clip,
+        uuid,
+    )
+    .execute(&mut *connection)
+    .await?;
+    get_tag(connection, uuid).await
+}
+
+/// Remove a tag from a clip; the tag itself is kept even if no clips have it.
+///
+/// # Returns
+///
+/// The tag that was removed from the clip.
+#[instrument(skip(connection))]
+pub async fn untag_clip(
+    connection: &mut SqliteConnection,
+    clip: String,
+    name: &str,
+) -> Result<Tag, crate::Error> {
+    let name = prepare_name(name)?;
+    let uuid = sqlx::query_scalar!(
+        r#"
+        DELETE FROM clip_tags
+        WHERE clip = $1 AND tag = (SELECT uuid FROM tags WHERE name = $2)
+        RETURNING tag
+        "#,
+        clip,
+        name,
+    )
+    .fetch_one(&mut *connection)
+    .await?;
+    get_tag(connection, uuid).await
+}
+
+/// Find the names of the tags of each of the given clips in a single query.
+///
+/// # Returns
+///
+/// A map from each clip's primary key to its tag names; clips without tags are left out.
+#[instrument(skip(connection))]
+pub async fn tags_for_clips(
+    connection: &mut SqliteConnection,
+    clip_uuids: &[String],
+) -> Result<HashMap<String, Vec<String>>, crate::Error> {
+    let clip_uuids = serde_json::to_string(clip_uuids)?;
+    let rows = sqlx::query!(
+        r#"
+        SELECT clip_tags.clip, tags.name
+        FROM clip_tags
+        JOIN tags ON tags.uuid = clip_tags.tag
+        WHERE clip_tags.clip IN (SELECT value FROM json_each($1))
+        ORDER BY tags.name
+        "#,
+        clip_uuids,
+    )
+    .fetch_all(&mut *connection)
+    .await?;
+
+    let mut tags: HashMap<String, Vec<String>> = HashMap::new();
+    for row in rows {
+        tags.entry(row.clip).or_default().push(row.name);
+    }
+    Ok(tags)
+}
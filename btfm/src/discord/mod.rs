@@ -2,10 +2,12 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
+use rand::{distributions::WeightedIndex, prelude::*};
 use serenity::prelude::*;
 use tokio::sync::mpsc;
 
 use crate::config::Config;
+use crate::matching::PhraseIndex;
 use crate::transcribe::Transcriber;
 
 pub struct BtfmData {
@@ -13,6 +15,8 @@ pub struct BtfmData {
     pub config: Config,
     /// Service to handle transcription requests
     pub transcriber: Transcriber,
+    /// In-memory index of phrases that trigger clips
+    pub phrase_index: PhraseIndex,
     /// Map ssrcs to Users
     users: HashMap<u32, User>,
     // Map user IDs to ssrc
@@ -47,9 +51,15 @@ impl BtfmData {
             .build()
             .expect("Unable to build a basic HTTP client");
         let transcriber = Transcriber::new(&config).expect("Unable to build transcriber");
+        let phrase_index = PhraseIndex::default();
+        phrase_index
+            .rebuild(&mut db.acquire().await.expect("Unable to connect to database"))
+            .await
+            .expect("Unable to build the phrase index");
         BtfmData {
             config,
             transcriber,
+            phrase_index,
             users: HashMap::new(),
             ssrc_map: HashMap::new(),
             user_history: HashMap::new(),
@@ -58,6 +68,12 @@ impl BtfmData {
             http_client,
         }
     }
+    /// The Discord user ID of the user sending audio with the given ssrc, if it's known.
+    fn speaker(&self, ssrc: u32) -> Option<u64> {
+        self.ssrc_map
+            .iter()
+            .find_map(|(user_id, user_ssrc)| (*user_ssrc == ssrc).then_some(*user_id))
+    }
 }
 
 /// Represents an active user in a voice channel.
@@ -75,5 +91,43 @@ impl User {
     }
 }
 
+/// Choose an item at random, where each item's chance of being chosen is proportional to its weight.
+///
+/// If every weight is zero, an item is chosen uniformly.
+fn choose_weighted<T>(mut items: Vec<T>, weight: impl Fn(&T) -> f64) -> Option<T> {
+    if items.is_empty() {
+        return None;
+    }
+    let mut rng = thread_rng();
+    let index = match WeightedIndex::new(items.iter().map(weight)) {
+        Ok(distribution) => distribution.sample(&mut rng),
+        Err(_) => rng.gen_range(0..items.len()),
+    };
+    Some(items.swap_remove(index))
+}
+
 pub mod text;
 pub mod voice;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn choose_weighted_skips_zero_weights() {
+        for _ in 0..100 {
+            let chosen = choose_weighted(vec![(1, 0.0), (2, 1.0), (3, 0.0)], |item| item.1);
+            assert_eq!(chosen, Some((2, 1.0)));
+        }
+    }
+
+    #[test]
+    fn choose_weighted_all_zero() {
+        let chosen = choose_weighted(vec![(1, 0.0), (2, 0.0)], |item| item.1);
+        assert!(chosen.is_some());
+        assert_eq!(
+            choose_weighted(Vec::<(u8, f64)>::new(), |item| item.1),
+            None
+        );
+    }
+}
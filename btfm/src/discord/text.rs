@@ -1,8 +1,8 @@
 //! Handlers for Discord non-voice events.
 
-use std::{sync::Arc, time::Duration};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
-use rand::prelude::IteratorRandom;
+use btfm_api_structs::AvailabilityWindow;
 use serenity::{
     async_trait,
     client::{Context, EventHandler},
@@ -39,8 +39,45 @@ async fn play_clip_at_interval(call: Arc<Mutex<Call>>, db_pool: SqlitePool) {
         {
             let mut conn = db_pool.acquire().await.unwrap();
             if let Ok(clips) = crate::db::clips_list(&mut conn).await {
-                if let Some(clip) = select_clip(clips) {
+                let uuids = clips
+                    .iter()
+                    .map(|clip| clip.uuid.clone())
+                    .collect::<Vec<_>>();
+                let tags = match crate::db::tags_for_clips(&mut conn, &uuids).await {
+                    Ok(tags) => tags,
+                    Err(err) => {
+                        error!(err = %err, "Failed to look up clip tags; skipping the random clip");
+                        continue;
+                    }
+                };
+                let availability = match crate::db::availability_for_clips(&mut conn, &uuids).await
+                {
+                    Ok(availability) => availability,
+                    Err(err) => {
+                        error!(err = %err, "Failed to look up clip availability; skipping the random clip");
+                        continue;
+                    }
+                };
+                if let Some(mut clip) =
+                    select_clip(clips, &tags, &availability, config.clip_cooldown)
+                {
                     info!("Playing a random clip to keep things spicy");
+                    if let Err(err) = crate::db::mark_played(&mut conn, &mut clip).await {
+                        error!(err = %err, "Failed to mark the random clip as played");
+                    }
+                    if let Err(err) = crate::db::record_play(
+                        &mut conn,
+                        &clip.uuid,
+                        crate::db::Trigger::Random,
+                        None,
+                        None,
+                        None,
+                        None,
+                    )
+                    .await
+                    {
+                        error!(err = %err, "Failed to record the clip in the play history");
+                    }
                     call.lock()
                         .await
                         .enqueue_input(
@@ -54,10 +91,27 @@ async fn play_clip_at_interval(call: Arc<Mutex<Call>>, db_pool: SqlitePool) {
     }
 }
 
+/// Pick a clip at random according to the clip weights; clips that are disabled, have a weight
+/// of 0, are cooling down, are muted by their tags, or are outside their availability windows
+/// are never picked.
+///
 /// This function only exists to work around the compiler being upset that the RNG might be used
 /// after an await, and even dropping it immediately doesn't help.
-fn select_clip(clips: Vec<Clip>) -> Option<Clip> {
-    clips.into_iter().choose(&mut rand::thread_rng())
+fn select_clip(
+    clips: Vec<Clip>,
+    tags: &HashMap<String, Vec<String>>,
+    availability: &HashMap<String, Vec<AvailabilityWindow>>,
+    cooldown: u64,
+) -> Option<Clip> {
+    let now = chrono::Utc::now().naive_utc();
+    let local_now = crate::availability::local_now();
+    let clips = clips
+        .into_iter()
+        .filter(|clip| clip.enabled && clip.weight > 0.0 && !clip.cooling_down(cooldown, now))
+        .filter(|clip| !crate::db::is_muted(tags, &clip.uuid))
+        .filter(|clip| crate::availability::is_available(availability, &clip.uuid, local_now))
+        .collect();
+    super::choose_weighted(clips, |clip| clip.weight)
 }
 
 pub struct Handler;
@@ -20,6 +20,7 @@ use uuid::Uuid;
 
 use super::{BtfmData, User};
 use crate::db;
+use crate::transcribe::Transcript;
 
 /// Return an AudioSource to greet a new user (or the channel at large).
 pub async fn hello_there(event_name: &str) -> Option<songbird::input::File<PathBuf>> {
@@ -79,20 +80,28 @@ impl VoiceEventHandler for Receiver {
                     }
 
                     let transcriber = &btfm_data.transcriber.clone();
+                    let speaker = btfm_data.speaker(*ssrc);
                     let user = btfm_data.users.entry(*ssrc).or_insert_with(User::new);
 
                     // The user just started talking.
                     if user.transcriber.is_none() {
                         let (audio_sender, audio_receiver) = mpsc::channel(2048);
-                        let span =
-                            tracing::info_span!("stream", id = %Uuid::now_v7(), ssrc = %ssrc);
+                        let span = tracing::info_span!(
+                            "stream",
+                            id = %Uuid::now_v7(),
+                            ssrc = %ssrc,
+                            speaker = ?speaker
+                        );
                         info!(parent: &span, "Beginning new transcription stream");
-                        let text_receiver =
-                            transcriber.stream(audio_receiver).instrument(span).await;
+                        let text_receiver = transcriber
+                            .stream(audio_receiver, speaker)
+                            .instrument(span)
+                            .await;
                         tokio::task::spawn(handle_text(
                             self.btfm_data.clone(),
                             self.http.clone(),
                             self.call.clone(),
+                            *ssrc,
                             text_receiver,
                         ));
                         user.transcriber = Some(audio_sender);
@@ -163,18 +172,18 @@ async fn handle_text(
     btfm_data: Arc<Mutex<BtfmData>>,
     http: Arc<serenity::http::Http>,
     call: Arc<Mutex<Call>>,
-    text_receiver: oneshot::Receiver<String>,
+    ssrc: u32,
+    text_receiver: oneshot::Receiver<Transcript>,
 ) {
-    lazy_static::lazy_static! {
-        static ref RE: Regex = Regex::new(r"[^\w\s]").unwrap();
-    }
-
-    let punctuated_text = text_receiver.await.unwrap_or_default();
+    let Transcript {
+        text: punctuated_text,
+        speaker,
+    } = text_receiver.await.unwrap_or_default();
     if punctuated_text.trim().is_empty() {
         debug!("It didn't sound like anything to the bot");
         return;
     }
-    let text = RE.replace_all(&punctuated_text, "").to_lowercase();
+    let text = crate::normalize::normalize(&punctuated_text);
 
     let current_time = chrono::Utc::now().naive_utc();
     let mut btfm = btfm_data.lock().await;
@@ -215,7 +224,7 @@ async fn handle_text(
 
     let rate_adjuster = btfm.config.rate_adjuster;
     let mut conn = btfm.db.acquire().await.unwrap();
-    if !text.contains("excuse me")
+    if !bypasses_rate_limit(&text)
         && rate_limit(
             current_time - db::last_play_time(&mut conn).await,
             rate_adjuster,
@@ -226,11 +235,32 @@ async fn handle_text(
         return;
     }
 
-    let clips = db::match_phrase(&mut conn, &text).await.unwrap();
-    let clip_count = clips.len();
-    let clip = clips.into_iter().choose(&mut rand::thread_rng());
-    if let Some(mut clip) = clip {
+    let matches = db::match_phrase(&mut conn, &btfm.phrase_index, &punctuated_text, speaker)
+        .await
+        .unwrap();
+    let clip_count = matches.len();
+    let best_match = select_best_match(matches);
+    if let Some(db::ClipMatch {
+        mut clip,
+        score,
+        phrase,
+        ..
+    }) = best_match
+    {
         db::mark_played(&mut conn, &mut clip).await.unwrap();
+        if let Err(err) = db::record_play(
+            &mut conn,
+            &clip.uuid,
+            db::Trigger::Phrase,
+            speaker,
+            Some(ssrc),
+            Some(&punctuated_text),
+            Some(&phrase),
+        )
+        .await
+        {
+            error!(err = %err, "Failed to record the clip in the play history");
+        }
         let phrases = db::phrases_for_clip(&mut conn, clip.uuid.clone())
             .await
             .unwrap_or_else(|_| vec![])
@@ -240,8 +270,8 @@ async fn handle_text(
             .join(", ");
         let msg = format!(
             "This technological terror matched against {} clips;
-            ```{}``` was randomly selected. Phrases that would trigger this clip: {}",
-            clip_count, &clip, phrases
+            ```{}``` was selected with a score of {:.2}. Phrases that would trigger this clip: {}",
+            clip_count, &clip, score, phrases
         );
         btfm.status_report = Some(format!(
             "The last clip that was played, described as \"{}\", is triggered by the phrases {}.",
@@ -260,6 +290,36 @@ async fn handle_text(
     }
 }
 
+/// Pick one of the best-scoring matches, breaking ties randomly according to the clip weights.
+///
+/// A weight of 0 only keeps a clip from being picked at random, so a clip with a weight of 0
+/// loses ties to clips with any other weight, but ties between such clips are broken uniformly.
+fn select_best_match(matches: Vec<db::ClipMatch>) -> Option<db::ClipMatch> {
+    let best_score = matches
+        .iter()
+        .map(|clip_match| clip_match.score)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let best_matches = matches
+        .into_iter()
+        .filter(|clip_match| clip_match.score >= best_score)
+        .collect();
+    super::choose_weighted(best_matches, |clip_match| clip_match.clip.weight)
+}
+
+/// Politely asking for a clip skips rate limiting.
+///
+/// `text` should already be normalized with [`crate::normalize::normalize`].
+pub fn bypasses_rate_limit(text: &str) -> bool {
+    text.contains("excuse me")
+}
+
+/// The chance, from 0 to 1, that a clip is played after the given time since the last one.
+///
+/// See [`rate_limit`] for details.
+pub fn play_chance(since_last_play: chrono::Duration, rate_adjuster: f64) -> f64 {
+    1.0 - (-since_last_play.num_seconds() as f64 / rate_adjuster).exp()
+}
+
 /// Return true if we should not play a clip (i.e., we are rate limited).
 ///
 /// # Arguments
@@ -283,7 +343,7 @@ fn rate_limit(
         "It's been {:?} since the last time a clip was played",
         since_last_play
     );
-    let play_chance = 1.0 - (-since_last_play.num_seconds() as f64 / rate_adjuster).exp();
+    let play_chance = play_chance(since_last_play, rate_adjuster);
     info!(
         "Clips have a {} percent chance (repeating of course) of being played",
         play_chance * 100.0
@@ -347,7 +407,77 @@ fn all_together_now(text: &str) -> Option<String> {
 
 #[cfg(test)]
 mod tests {
-    use super::all_together_now;
+    use super::{all_together_now, bypasses_rate_limit, play_chance, select_best_match};
+    use crate::db;
+
+    fn clip_match(title: &str, weight: f64, score: f64) -> db::ClipMatch {
+        let now = chrono::Utc::now().naive_utc();
+        db::ClipMatch {
+            clip: db::Clip {
+                uuid: title.to_string(),
+                created_on: now,
+                last_played: now,
+                plays: 0,
+                speech_detected: None,
+                audio_file: format!("clips/{title}.ogg"),
+                original_file_name: format!("{title}.ogg"),
+                title: title.to_string(),
+                description: None,
+                weight,
+                cooldown: None,
+                speech_detected_edited: false,
+                match_speech_detected: true,
+                enabled: true,
+                deleted_on: None,
+            },
+            phrase: "nice".to_string(),
+            score,
+            cooling_down: false,
+            excluded_by: None,
+            muted: false,
+            unavailable: false,
+        }
+    }
+
+    #[test]
+    fn test_select_best_match_zero_weight_tie() {
+        for _ in 0..200 {
+            let best = select_best_match(vec![
+                clip_match("zero", 0.0, 1.0),
+                clip_match("half", 0.5, 1.0),
+                clip_match("worse", 1.0, 0.5),
+            ])
+            .unwrap();
+            assert_eq!(best.clip.title, "half");
+        }
+
+        let mut picked = std::collections::HashSet::new();
+        for _ in 0..200 {
+            let best = select_best_match(vec![
+                clip_match("zero", 0.0, 1.0),
+                clip_match("also-zero", 0.0, 1.0),
+            ])
+            .unwrap();
+            picked.insert(best.clip.title);
+        }
+        assert_eq!(picked.len(), 2);
+    }
+
+    #[test]
+    fn test_normalized_transcript_bypasses_rate_limit() {
+        let text = crate::normalize::normalize("Excuse me! Can I get a clip?");
+        assert!(bypasses_rate_limit(&text));
+        assert!(!bypasses_rate_limit(&crate::normalize::normalize(
+            "Hello there! General Kenobi."
+        )));
+    }
+
+    #[test]
+    fn test_play_chance() {
+        assert_eq!(play_chance(chrono::Duration::zero(), 120.0), 0.0);
+        let chance = play_chance(chrono::Duration::seconds(120), 120.0);
+        assert!((chance - (1.0 - (-1.0_f64).exp())).abs() < f64::EPSILON);
+    }
 
     #[test]
     fn test_all_together() {
@@ -20,7 +20,7 @@ pub enum Error {
     #[error("Configuration file contains invalid values: {0}")]
     ConfigValueError(String),
     #[error("The Discord client encountered an error: {0}")]
-    Serenity(#[from] serenity::Error),
+    Serenity(Box<serenity::Error>),
     #[error("HTTP server encountered an error: {0}")]
     Server(std::io::Error),
     #[error("Tokio task failed: {0}")]
@@ -29,6 +29,12 @@ pub enum Error {
     TokioOneshot(#[from] tokio::sync::oneshot::error::RecvError),
     #[error("Client request is invalid")]
     BadRequest,
+    #[error("The request conflicts with an operation already in progress")]
+    Conflict,
+    #[error("Invalid regular expression: {0}")]
+    InvalidRegex(#[from] regex::Error),
+    #[error("Unable to build the phrase index: {0}")]
+    PhraseIndex(#[from] aho_corasick::BuildError),
     #[error("File not found")]
     NotFound,
     #[error("An HTTP error occurred")]
@@ -45,11 +51,22 @@ pub enum Error {
     Json(#[from] serde_json::Error),
 }
 
+impl From<serenity::Error> for Error {
+    fn from(err: serenity::Error) -> Self {
+        Error::Serenity(Box::new(err))
+    }
+}
+
+pub mod availability;
 pub mod cli;
 pub mod config;
 pub mod db;
 pub mod discord;
+pub mod matching;
 pub(crate) mod mimic;
+pub mod normalize;
+pub mod retranscribe;
 pub mod transcode;
 pub mod transcribe;
+pub mod trash;
 pub mod web;
@@ -0,0 +1,201 @@
+// SPDX-License-Identifier: GPL-2.0-or-later
+//! Find phrases that overlap with each other or with clips' detected speech.
+//!
+//! Overlapping phrases aren't errors, but they often explain why an unexpected clip played.
+//! Only literal phrases are checked; regular expressions can't be compared this way.
+use std::collections::HashMap;
+
+use btfm_api_structs::{ConflictKind, PhraseConflict};
+
+use super::contains_words;
+use crate::db;
+
+/// Report duplicate, overlapping, and too-short phrases.
+///
+/// # Arguments
+///
+/// `phrases` - All the phrases, as returned by [`db::list_phrases`].
+///
+/// `speech` - The primary key and detected speech of each clip that is matched on its speech.
+///
+/// `min_length` - Phrases with fewer characters than this, not counting spaces, are too short.
+pub fn find_conflicts(
+    phrases: &[db::Phrase],
+    speech: &[(String, String)],
+    min_length: usize,
+) -> Vec<PhraseConflict> {
+    let phrases = phrases
+        .iter()
+        .filter(|phrase| phrase.kind == db::PhraseKind::Literal)
+        .collect::<Vec<_>>();
+    let mut conflicts = vec![];
+
+    let mut first_seen: HashMap<&str, &db::Phrase> = HashMap::new();
+    for phrase in phrases.iter() {
+        match first_seen.get(phrase.phrase.as_str()) {
+            Some(original) => conflicts.push(conflict(
+                ConflictKind::Duplicate,
+                phrase,
+                Some((&original.uuid, &original.phrase)),
+            )),
+            None => {
+                first_seen.insert(&phrase.phrase, phrase);
+            }
+        }
+    }
+
+    // A shorter phrase only changes what plays if it triggers clips the longer one doesn't.
+    for phrase in phrases.iter() {
+        for other in phrases.iter() {
+            if phrase.phrase != other.phrase
+                && appears_in(phrase, &other.phrase)
+                && !phrase.clips.iter().all(|clip| other.clips.contains(clip))
+            {
+                conflicts.push(conflict(
+                    ConflictKind::Substring,
+                    phrase,
+                    Some((&other.uuid, &other.phrase)),
+                ));
+            }
+        }
+    }
+
+    for phrase in phrases.iter() {
+        if phrase.phrase.chars().filter(|c| !c.is_whitespace()).count() < min_length {
+            conflicts.push(conflict(ConflictKind::TooShort, phrase, None));
+        }
+    }
+
+    for phrase in phrases.iter() {
+        for (clip, speech) in speech {
+            if !phrase.clips.contains(clip) && appears_in(phrase, speech) {
+                conflicts.push(conflict(
+                    ConflictKind::SpeechDetected,
+                    phrase,
+                    Some((clip, speech)),
+                ));
+            }
+        }
+    }
+
+    conflicts
+}
+
+/// Whether the phrase would match the text exactly, respecting its substring setting.
+fn appears_in(phrase: &db::Phrase, text: &str) -> bool {
+    if phrase.substring {
+        text.contains(&phrase.phrase)
+    } else {
+        contains_words(&phrase.phrase, text)
+    }
+}
+
+fn conflict(
+    kind: ConflictKind,
+    phrase: &db::Phrase,
+    other: Option<(&String, &String)>,
+) -> PhraseConflict {
+    PhraseConflict {
+        kind,
+        phrase: phrase.uuid.clone(),
+        text: phrase.phrase.clone(),
+        other: other.map(|(uuid, _)| uuid.clone()),
+        other_text: other.map(|(_, text)| text.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn phrase(uuid: &str, text: &str, clips: &[&str]) -> db::Phrase {
+        db::Phrase {
+            uuid: uuid.into(),
+            phrase: text.into(),
+            similarity_threshold: None,
+            substring: false,
+            kind: db::PhraseKind::Literal,
+            clips: clips.iter().map(|clip| clip.to_string()).collect(),
+            speakers: Default::default(),
+        }
+    }
+
+    fn kinds(conflicts: &[PhraseConflict]) -> Vec<(ConflictKind, &str, Option<&str>)> {
+        conflicts
+            .iter()
+            .map(|c| (c.kind, c.phrase.as_str(), c.other.as_deref()))
+            .collect()
+    }
+
+    #[test]
+    fn duplicates() {
+        let phrases = [
+            phrase("a", "hello there", &["1"]),
+            phrase("b", "hello there", &["2"]),
+        ];
+        assert_eq!(
+            kinds(&find_conflicts(&phrases, &[], 4)),
+            vec![(ConflictKind::Duplicate, "b", Some("a"))]
+        );
+    }
+
+    #[test]
+    fn substrings() {
+        let phrases = [
+            phrase("a", "hello", &["1"]),
+            phrase("b", "hello there", &["2"]),
+            phrase("c", "hello there general kenobi", &["2", "3"]),
+            phrase("d", "othello", &["4"]),
+        ];
+        assert_eq!(
+            kinds(&find_conflicts(&phrases, &[], 4)),
+            vec![
+                (ConflictKind::Substring, "a", Some("b")),
+                (ConflictKind::Substring, "a", Some("c")),
+            ]
+        );
+    }
+
+    #[test]
+    fn substring_mode() {
+        let mut hell = phrase("a", "hell", &["1"]);
+        hell.substring = true;
+        let phrases = [hell, phrase("b", "hello there", &["2"])];
+        assert_eq!(
+            kinds(&find_conflicts(&phrases, &[], 4)),
+            vec![(ConflictKind::Substring, "a", Some("b"))]
+        );
+    }
+
+    #[test]
+    fn too_short() {
+        let phrases = [phrase("a", "a b", &["1"]), phrase("b", "okay", &["2"])];
+        assert_eq!(
+            kinds(&find_conflicts(&phrases, &[], 4)),
+            vec![(ConflictKind::TooShort, "a", None)]
+        );
+    }
+
+    #[test]
+    fn speech_detected() {
+        let phrases = [
+            phrase("a", "they found me", &["1"]),
+            phrase("b", "run for it", &["2"]),
+        ];
+        let speech = [(
+            "2".to_string(),
+            "i dont know how but they found me".to_string(),
+        )];
+        assert_eq!(
+            kinds(&find_conflicts(&phrases, &speech, 4)),
+            vec![(ConflictKind::SpeechDetected, "a", Some("2"))]
+        );
+    }
+
+    #[test]
+    fn regex_ignored() {
+        let mut regex = phrase("a", "h", &["1"]);
+        regex.kind = db::PhraseKind::Regex;
+        assert!(find_conflicts(&[regex], &[], 4).is_empty());
+    }
+}
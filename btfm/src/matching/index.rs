@@ -0,0 +1,478 @@
+// SPDX-License-Identifier: GPL-2.0-or-later
+//! An in-memory index of everything that can trigger a clip.
+//!
+//! Every transcript is compared to every phrase, so rather than loading the phrases from the
+//! database each time, they're kept in memory. Literal phrases are compiled into Aho-Corasick
+//! automatons which find all of them in a single pass over the transcript.
+//!
+//! The index must be rebuilt whenever clips or phrases change.
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, PoisonError, RwLock};
+
+use aho_corasick::AhoCorasick;
+use regex::Regex;
+use sqlx::SqliteConnection;
+use tracing::{error, instrument};
+
+use super::{align, compile_regex, contains_words, tokenize, Word};
+use crate::config::Matching;
+use crate::db;
+
+/// How an [`Entry`] is matched against text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntryKind {
+    /// Match the entry's words, in order, against whole words in the text.
+    Words,
+    /// Match the entry anywhere in the text, even inside other words.
+    Substring,
+    /// The entry is a regular expression.
+    Regex,
+}
+
+/// Something that triggers clips; either a phrase or a clip's detected speech.
+#[derive(Clone, Debug)]
+pub struct Entry {
+    /// The primary keys of the clips that are triggered.
+    pub clips: Vec<String>,
+    /// The phrase that triggers the clips.
+    pub phrase: String,
+    pub kind: EntryKind,
+    /// Overrides the configured minimum similarity for fuzzy matches of this entry.
+    pub similarity_threshold: Option<f64>,
+    /// Who can trigger the entry.
+    pub speakers: db::Speakers,
+}
+
+/// An entry from the index that matched some text.
+#[derive(Clone, Debug)]
+pub struct Candidate {
+    /// The primary key of the clip that is triggered.
+    pub clip: String,
+    /// The phrase that matched the text.
+    pub phrase: String,
+    /// How closely the phrase matched, from 0 to 1 where 1 is an exact match.
+    pub score: f64,
+}
+
+/// A handle to the shared phrase index; clones refer to the same index.
+#[derive(Clone)]
+pub struct PhraseIndex {
+    index: Arc<RwLock<Index>>,
+}
+
+impl Default for PhraseIndex {
+    fn default() -> Self {
+        PhraseIndex::new(Vec::new(), Vec::new()).expect("An empty index is always valid")
+    }
+}
+
+impl PhraseIndex {
+    /// Construct an index with the given entries and exclusions.
+    ///
+    /// Exclusions are pairs of a clip's primary key and a phrase that vetoes the clip.
+    pub fn new(
+        entries: Vec<Entry>,
+        exclusions: Vec<(String, String)>,
+    ) -> Result<Self, crate::Error> {
+        Ok(PhraseIndex {
+            index: Arc::new(RwLock::new(Index::new(entries, exclusions)?)),
+        })
+    }
+
+    /// Replace the contents of the index with all the clips and phrases in the database.
+    ///
+    /// Disabled clips are left out. Clip speech is only included if the clip matches on it and
+    /// it has at least the configured minimum number of words.
+    #[instrument(skip_all)]
+    pub async fn rebuild(&self, connection: &mut SqliteConnection) -> Result<(), crate::Error> {
+        let config = crate::CONFIG.get().expect("Initialize the config");
+        let min_words = config.matching.speech_detected_min_words;
+        let clips = db::clips_list(connection)
+            .await?
+            .into_iter()
+            .filter(|clip| clip.enabled)
+            .collect::<Vec<_>>();
+        let enabled = clips
+            .iter()
+            .map(|clip| clip.uuid.clone())
+            .collect::<HashSet<_>>();
+        let phrases = db::list_phrases(connection).await?;
+        let exclusions = db::list_exclusions(connection, None)
+            .await?
+            .into_iter()
+            .map(|exclusion| (exclusion.clip, exclusion.phrase))
+            .collect();
+
+        let speech = clips.into_iter().filter_map(|clip| {
+            clip.speech_detected
+                .filter(|_| clip.match_speech_detected)
+                .filter(|speech| speech.split_whitespace().count() >= min_words)
+                .map(|speech| Entry {
+                    clips: vec![clip.uuid],
+                    phrase: speech,
+                    kind: EntryKind::Words,
+                    similarity_threshold: None,
+                    speakers: Default::default(),
+                })
+        });
+        let phrases = phrases.into_iter().filter_map(|phrase| {
+            let clips = phrase
+                .clips
+                .into_iter()
+                .filter(|clip| enabled.contains(clip))
+                .collect::<Vec<_>>();
+            (!clips.is_empty()).then_some(Entry {
+                kind: match phrase.kind {
+                    db::PhraseKind::Regex => EntryKind::Regex,
+                    db::PhraseKind::Literal if phrase.substring => EntryKind::Substring,
+                    db::PhraseKind::Literal => EntryKind::Words,
+                },
+                clips,
+                phrase: phrase.phrase,
+                similarity_threshold: phrase.similarity_threshold,
+                speakers: phrase.speakers,
+            })
+        });
+        let index = Index::new(speech.chain(phrases).collect(), exclusions)?;
+        tracing::info!(entries = index.entries.len(), "Rebuilt the phrase index");
+
+        *self.index.write().unwrap_or_else(PoisonError::into_inner) = index;
+        Ok(())
+    }
+
+    /// Find every entry that matches the transcript and that the speaker can trigger.
+    ///
+    /// Literal entries are matched against the transcript once it's been normalized, while
+    /// regular expressions are matched against it as it was transcribed, so patterns like `\d+`
+    /// still match numbers. The speaker is a Discord user ID, if it's known.
+    pub fn find(
+        &self,
+        transcript: &str,
+        speaker: Option<u64>,
+        config: &Matching,
+    ) -> Vec<Candidate> {
+        let text = crate::normalize::normalize(transcript);
+        self.index
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .find(&text, transcript, speaker, config)
+    }
+
+    /// Find the first of the clip's exclusion phrases that appears in the text, if any.
+    pub fn excluded_by(&self, clip: &str, text: &str) -> Option<String> {
+        self.index
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .exclusions
+            .get(clip)?
+            .iter()
+            .find(|phrase| contains_words(phrase, text))
+            .cloned()
+    }
+}
+
+struct Index {
+    entries: Vec<Entry>,
+    /// The words of each entry, for fuzzy matching; only populated for [`EntryKind::Words`].
+    words: Vec<Vec<Word>>,
+    /// Finds [`EntryKind::Words`] entries in the text after it's been normalized into words
+    /// separated by single spaces.
+    word_automaton: AhoCorasick,
+    /// Maps pattern IDs in the word automaton to entries.
+    word_patterns: Vec<usize>,
+    /// Finds [`EntryKind::Substring`] entries in the text as-is.
+    substring_automaton: AhoCorasick,
+    /// Maps pattern IDs in the substring automaton to entries.
+    substring_patterns: Vec<usize>,
+    /// Compiled [`EntryKind::Regex`] entries and their entry index.
+    regexes: Vec<(usize, Regex)>,
+    /// Maps clips to the phrases that veto them.
+    exclusions: HashMap<String, Vec<String>>,
+}
+
+impl Index {
+    fn new(entries: Vec<Entry>, exclusions: Vec<(String, String)>) -> Result<Self, crate::Error> {
+        let mut words = Vec::with_capacity(entries.len());
+        let mut word_strings = vec![];
+        let mut word_patterns = vec![];
+        let mut substring_strings = vec![];
+        let mut substring_patterns = vec![];
+        let mut regexes = vec![];
+
+        for (id, entry) in entries.iter().enumerate() {
+            let entry_words = match entry.kind {
+                EntryKind::Words => {
+                    let tokens = tokenize(&entry.phrase);
+                    if !tokens.is_empty() {
+                        word_strings.push(tokens.join(" "));
+                        word_patterns.push(id);
+                    }
+                    tokens
+                        .into_iter()
+                        .map(|word| Word::new(word, true))
+                        .collect()
+                }
+                EntryKind::Substring => {
+                    if !entry.phrase.is_empty() {
+                        substring_strings.push(entry.phrase.clone());
+                        substring_patterns.push(id);
+                    }
+                    vec![]
+                }
+                EntryKind::Regex => {
+                    match compile_regex(&entry.phrase) {
+                        Ok(regex) => regexes.push((id, regex)),
+                        Err(err) => {
+                            error!(phrase = %entry.phrase, err = %err, "Skipping invalid regex")
+                        }
+                    }
+                    vec![]
+                }
+            };
+            words.push(entry_words);
+        }
+
+        Ok(Index {
+            entries,
+            words,
+            word_automaton: AhoCorasick::new(word_strings)?,
+            word_patterns,
+            substring_automaton: AhoCorasick::new(substring_strings)?,
+            substring_patterns,
+            regexes,
+            exclusions: exclusions.into_iter().fold(
+                HashMap::new(),
+                |mut exclusions, (clip, phrase)| {
+                    exclusions.entry(clip).or_insert_with(Vec::new).push(phrase);
+                    exclusions
+                },
+            ),
+        })
+    }
+
+    /// Match entries against the normalized text, or regular expressions against the transcript.
+    fn find(
+        &self,
+        text: &str,
+        transcript: &str,
+        speaker: Option<u64>,
+        config: &Matching,
+    ) -> Vec<Candidate> {
+        let mut matched = HashSet::new();
+        let mut candidates = vec![];
+        let mut add_candidate = |id: usize, score: f64| {
+            let entry = &self.entries[id];
+            if entry.speakers.allows(speaker) && matched.insert(id) {
+                candidates.extend(entry.clips.iter().map(|clip| Candidate {
+                    clip: clip.clone(),
+                    phrase: entry.phrase.clone(),
+                    score,
+                }));
+            }
+        };
+
+        let words = tokenize(text).join(" ");
+        for found in self.word_automaton.find_overlapping_iter(&words) {
+            let starts_word = found.start() == 0 || words.as_bytes()[found.start() - 1] == b' ';
+            let ends_word = found.end() == words.len() || words.as_bytes()[found.end()] == b' ';
+            if starts_word && ends_word {
+                add_candidate(self.word_patterns[found.pattern().as_usize()], 1.0);
+            }
+        }
+
+        for found in self.substring_automaton.find_overlapping_iter(text) {
+            add_candidate(self.substring_patterns[found.pattern().as_usize()], 1.0);
+        }
+
+        for (id, regex) in self.regexes.iter() {
+            if regex.is_match(transcript) {
+                add_candidate(*id, 1.0);
+            }
+        }
+
+        if config.fuzzy {
+            let text_words = Word::split(text, config.phonetic);
+            for id in self.word_patterns.iter().copied() {
+                let threshold = self.entries[id]
+                    .similarity_threshold
+                    .unwrap_or(config.similarity_threshold);
+                let similarity = align(&self.words[id], &text_words);
+                if similarity >= threshold {
+                    add_candidate(id, similarity);
+                }
+            }
+        }
+
+        candidates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(clip: &str, phrase: &str, kind: EntryKind) -> Entry {
+        Entry {
+            clips: vec![clip.to_string()],
+            phrase: phrase.to_string(),
+            kind,
+            similarity_threshold: None,
+            speakers: Default::default(),
+        }
+    }
+
+    fn index() -> PhraseIndex {
+        PhraseIndex::new(
+            vec![
+                entry("they-found-me", "they found me", EntryKind::Words),
+                entry("cat", "cat", EntryKind::Words),
+                entry("concat", "cat", EntryKind::Substring),
+                entry("hello-there", "(hello|hi) there", EntryKind::Regex),
+            ],
+            vec![("cat".to_string(), "not a cat".to_string())],
+        )
+        .unwrap()
+    }
+
+    fn clips(candidates: Vec<Candidate>) -> Vec<String> {
+        let mut clips: Vec<String> = candidates.into_iter().map(|c| c.clip).collect();
+        clips.sort();
+        clips
+    }
+
+    #[test]
+    fn whole_words() {
+        let found = index().find(
+            "i dont know how but they found me",
+            None,
+            &Matching::default(),
+        );
+        assert_eq!(clips(found), vec!["they-found-me"]);
+    }
+
+    #[test]
+    fn substring() {
+        let found = index().find("concatenate", None, &Matching::default());
+        assert_eq!(clips(found), vec!["concat"]);
+
+        let found = index().find("the cat sat", None, &Matching::default());
+        assert_eq!(clips(found), vec!["cat", "concat"]);
+    }
+
+    #[test]
+    fn regex() {
+        let found = index().find("well hi there", None, &Matching::default());
+        assert_eq!(clips(found), vec!["hello-there"]);
+    }
+
+    #[test]
+    fn regex_matches_transcript() {
+        let index = PhraseIndex::new(
+            vec![entry("apples", r"\d+ apples", EntryKind::Regex)],
+            vec![],
+        )
+        .unwrap();
+        let found = index.find("I have 12 apples.", None, &Matching::default());
+        assert_eq!(clips(found), vec!["apples"]);
+    }
+
+    #[test]
+    fn fuzzy() {
+        let config = Matching {
+            fuzzy: true,
+            ..Default::default()
+        };
+        let found = index().find("but they find me", None, &config);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].clip, "they-found-me");
+        assert!(found[0].score < 1.0);
+
+        assert!(index()
+            .find("but they find me", None, &Matching::default())
+            .is_empty());
+    }
+
+    #[test]
+    fn threshold_override() {
+        let config = Matching {
+            fuzzy: true,
+            ..Default::default()
+        };
+        let index = PhraseIndex::new(
+            vec![Entry {
+                similarity_threshold: Some(0.7),
+                ..entry("clip", "i dont know how", EntryKind::Words)
+            }],
+            vec![],
+        )
+        .unwrap();
+        let found = index.find("well i dont know", None, &config);
+        assert_eq!(found[0].score, 0.75);
+    }
+
+    #[test]
+    fn shared_phrase() {
+        let index = PhraseIndex::new(
+            vec![Entry {
+                clips: vec!["nice-1".to_string(), "nice-2".to_string()],
+                ..entry("", "nice", EntryKind::Words)
+            }],
+            vec![],
+        )
+        .unwrap();
+        let found = index.find("very nice", None, &Matching::default());
+        assert_eq!(clips(found), vec!["nice-1", "nice-2"]);
+    }
+
+    #[test]
+    fn exclusions() {
+        let index = index();
+        assert_eq!(
+            index.excluded_by("cat", "that is not a cat"),
+            Some("not a cat".to_string())
+        );
+        assert_eq!(index.excluded_by("cat", "that is a cat"), None);
+        assert_eq!(index.excluded_by("concat", "that is not a cat"), None);
+    }
+
+    #[test]
+    fn speakers() {
+        let index = PhraseIndex::new(
+            vec![
+                Entry {
+                    speakers: db::Speakers {
+                        allowed: vec![1],
+                        denied: vec![],
+                    },
+                    ..entry("only-one", "in joke", EntryKind::Words)
+                },
+                Entry {
+                    speakers: db::Speakers {
+                        allowed: vec![],
+                        denied: vec![1],
+                    },
+                    ..entry("not-one", "in joke", EntryKind::Words)
+                },
+            ],
+            vec![],
+        )
+        .unwrap();
+        let config = Matching::default();
+        assert_eq!(
+            clips(index.find("in joke", Some(1), &config)),
+            vec!["only-one"]
+        );
+        assert_eq!(
+            clips(index.find("in joke", Some(2), &config)),
+            vec!["not-one"]
+        );
+        assert_eq!(clips(index.find("in joke", None, &config)), vec!["not-one"]);
+    }
+
+    #[test]
+    fn empty() {
+        assert!(PhraseIndex::default()
+            .find("anything", None, &Matching::default())
+            .is_empty());
+    }
+}
@@ -0,0 +1,189 @@
+// SPDX-License-Identifier: GPL-2.0-or-later
+//! Match trigger phrases against transcribed speech.
+//!
+//! Phrases and transcripts are compared as sequences of whole words, so the phrase "cat" matches
+//! "the cat sat" but not "concatenate".
+//!
+//! Speech-to-text regularly gets a word or two wrong, so in addition to exact matches phrases can
+//! optionally be matched approximately. Approximate matching works on words rather than
+//! characters: it finds the stretch of the transcript that needs the fewest word insertions,
+//! deletions, and substitutions to become the phrase. Substituting a word with one that is
+//! spelled similarly, or that sounds the same according to Double Metaphone, costs less than
+//! substituting an unrelated word.
+use cached::proc_macro::cached;
+use regex::{Regex, RegexBuilder};
+use rphonetic::{DoubleMetaphone, DoubleMetaphoneResult};
+
+mod conflicts;
+mod index;
+
+pub use conflicts::find_conflicts;
+pub use index::{Candidate, Entry, EntryKind, PhraseIndex};
+
+/// The cost of substituting a word with one that sounds the same; an unrelated word costs 1.
+const PHONETIC_SUBSTITUTION_COST: f64 = 0.1;
+
+/// Compile a regular expression phrase.
+///
+/// Patterns are matched case-insensitively. Compiled patterns are cached, so this is cheap to
+/// call repeatedly.
+#[cached(result = true, key = "String", convert = r#"{ pattern.to_string() }"#)]
+pub fn compile_regex(pattern: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern).case_insensitive(true).build()
+}
+
+/// Split text into lowercase words, discarding punctuation and whitespace.
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Check whether the words of the phrase appear, in order and uninterrupted, in the text.
+pub fn contains_words(phrase: &str, text: &str) -> bool {
+    let phrase = tokenize(phrase);
+    let text = tokenize(text);
+    !phrase.is_empty() && text.windows(phrase.len()).any(|window| window == phrase)
+}
+
+/// Compute how similar the phrase is to the closest matching run of words in the text.
+///
+/// This is the token-level edit distance between the phrase and the best-aligned substring of
+/// the text, normalized by the number of words in the phrase. The result is between 0 (nothing
+/// alike) and 1 (the phrase appears word-for-word).
+pub fn similarity(phrase: &str, text: &str, phonetic: bool) -> f64 {
+    align(&Word::split(phrase, phonetic), &Word::split(text, phonetic))
+}
+
+/// Align the phrase's words to the best-matching run of words in the text.
+///
+/// See [`similarity`] for details.
+fn align(phrase: &[Word], text: &[Word]) -> f64 {
+    if phrase.is_empty() {
+        return 0.0;
+    }
+
+    // Each row holds the cheapest alignment of the first `i` phrase words ending at each text
+    // word; the first row is all zeros since the phrase can start anywhere in the text.
+    let mut previous = vec![0.0; text.len() + 1];
+    let mut current = vec![0.0; text.len() + 1];
+    for (i, phrase_word) in phrase.iter().enumerate() {
+        current[0] = (i + 1) as f64;
+        for (j, text_word) in text.iter().enumerate() {
+            let substitute = previous[j] + phrase_word.substitution_cost(text_word);
+            let skip_phrase_word = previous[j + 1] + 1.0;
+            let skip_text_word = current[j] + 1.0;
+            current[j + 1] = substitute.min(skip_phrase_word).min(skip_text_word);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    let distance = previous.into_iter().fold(f64::INFINITY, f64::min);
+    (1.0 - distance / phrase.len() as f64).max(0.0)
+}
+
+/// A word along with its phonetic encoding, if phonetic matching is enabled.
+#[derive(Clone, Debug)]
+struct Word {
+    text: String,
+    sound: Option<DoubleMetaphoneResult>,
+}
+
+impl Word {
+    fn new(text: String, phonetic: bool) -> Self {
+        let sound = phonetic.then(|| DoubleMetaphone::default().double_metaphone(&text));
+        Word { text, sound }
+    }
+
+    /// Split text into words as done by [`tokenize`].
+    fn split(text: &str, phonetic: bool) -> Vec<Self> {
+        tokenize(text)
+            .into_iter()
+            .map(|word| Word::new(word, phonetic))
+            .collect()
+    }
+
+    fn sounds_like(&self, other: &Word) -> bool {
+        match (&self.sound, &other.sound) {
+            (Some(this), Some(that)) => {
+                let these = [this.primary(), this.alternate()];
+                let those = [that.primary(), that.alternate()];
+                these
+                    .iter()
+                    .any(|code| !code.is_empty() && those.contains(code))
+            }
+            _ => false,
+        }
+    }
+
+    /// The cost of replacing this word with another, between 0 and 1.
+    fn substitution_cost(&self, other: &Word) -> f64 {
+        if self.text == other.text {
+            return 0.0;
+        }
+        let spelling_cost = 1.0 - strsim::normalized_levenshtein(&self.text, &other.text);
+        if self.sounds_like(other) {
+            spelling_cost.min(PHONETIC_SUBSTITUTION_COST)
+        } else {
+            spelling_cost
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whole_words_only() {
+        assert!(!contains_words("cat", "concatenate"));
+        assert!(!contains_words("cat", "education"));
+        assert!(contains_words("cat", "the cat sat"));
+    }
+
+    #[test]
+    fn punctuation_ignored() {
+        assert!(contains_words(
+            "hello, there",
+            "well hello there general kenobi"
+        ));
+        assert!(!contains_words("hello there", "hello general kenobi there"));
+    }
+
+    #[test]
+    fn regex_compile() {
+        assert!(compile_regex("(hello|hi) there").is_ok());
+        assert!(compile_regex("(hello").is_err());
+    }
+
+    #[test]
+    fn exact_similarity() {
+        let similarity = similarity("they found me", "i dont know how but they found me", true);
+        assert_eq!(similarity, 1.0);
+    }
+
+    #[test]
+    fn phonetic_similarity() {
+        let similarity = similarity("but they found me", "bud they found me", true);
+        assert!(similarity > 0.9);
+    }
+
+    #[test]
+    fn phonetic_disabled() {
+        let similarity = similarity("but they found me", "bud they found me", false);
+        assert!(similarity < 0.95);
+    }
+
+    #[test]
+    fn missing_word() {
+        let similarity = similarity("i dont know how", "well i dont know", true);
+        assert_eq!(similarity, 0.75);
+    }
+
+    #[test]
+    fn unrelated_text() {
+        let similarity = similarity("they found me", "what a lovely day", true);
+        assert!(similarity < 0.5);
+    }
+}
@@ -0,0 +1,272 @@
+// SPDX-License-Identifier: GPL-2.0-or-later
+//! Normalize text so phrases can be compared with transcribed speech.
+//!
+//! Phrases, exclusions, and detected speech are normalized before they're stored, and
+//! transcripts are normalized before they're matched, so both sides always agree. Normalized
+//! text is lowercase words separated by single spaces:
+//!
+//! - Diacritics are removed and case is folded, so "Café" becomes "cafe".
+//! - Contractions ending in "n't", "'re", "'ve", "'ll", and "'m" are expanded, so "don't"
+//!   becomes "do not". Ambiguous ones, like "'s" and "'d", just lose their apostrophe.
+//! - Numbers written with ASCII digits are written out as words, so "2nd" becomes "second" and
+//!   "3.5" becomes "three point five".
+//! - Any other punctuation separates words.
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+/// The version of the normalization rules.
+///
+/// Increment this whenever [`normalize`] changes so text already in the database is
+/// re-normalized at startup; see [`crate::db::renormalize`].
+pub const VERSION: i64 = 2;
+
+const ONES: [&str; 20] = [
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+
+const TENS: [&str; 10] = [
+    "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+
+const SCALES: [(u64, &str); 6] = [
+    (1_000_000_000_000_000_000, "quintillion"),
+    (1_000_000_000_000_000, "quadrillion"),
+    (1_000_000_000_000, "trillion"),
+    (1_000_000_000, "billion"),
+    (1_000_000, "million"),
+    (1_000, "thousand"),
+];
+
+/// Normalize text for matching; see the module documentation for the rules.
+pub fn normalize(text: &str) -> String {
+    lazy_static::lazy_static! {
+        static ref TOKEN: regex::Regex = regex::Regex::new(
+            r"(?P<number>[0-9]+(?:,[0-9]{3})*(?:\.[0-9]+)?)(?:(?P<ordinal>st|nd|rd|th)\b)?|(?P<word>[\p{Alphabetic}']+)"
+        )
+        .unwrap();
+    }
+    let folded = fold(text);
+    let mut words = vec![];
+    for token in TOKEN.captures_iter(&folded) {
+        if let Some(number) = token.name("number") {
+            words.push(number_to_words(
+                number.as_str(),
+                token.name("ordinal").is_some(),
+            ));
+        } else if let Some(word) = token.name("word") {
+            if let Some(word) = expand_contraction(word.as_str()) {
+                words.push(word);
+            }
+        }
+    }
+    words.join(" ")
+}
+
+/// Fold case, remove diacritics, and make all apostrophes ASCII.
+fn fold(text: &str) -> String {
+    text.nfkd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .map(|c| match c {
+            '\u{2018}' | '\u{2019}' | '\u{02bc}' => '\'',
+            c => c,
+        })
+        .collect::<String>()
+        .replace('ß', "ss")
+}
+
+/// Expand a contraction, or drop the apostrophes from any other word.
+///
+/// # Returns
+///
+/// The expanded words, or `None` if the word was only apostrophes.
+fn expand_contraction(word: &str) -> Option<String> {
+    let word = word.trim_matches('\'');
+    if word.is_empty() {
+        return None;
+    }
+    let expanded = match word {
+        "won't" => "will not".to_string(),
+        "can't" | "cannot" => "can not".to_string(),
+        "shan't" => "shall not".to_string(),
+        "ain't" => "aint".to_string(),
+        _ => [
+            ("n't", " not"),
+            ("'re", " are"),
+            ("'ve", " have"),
+            ("'ll", " will"),
+            ("'m", " am"),
+        ]
+        .iter()
+        .find_map(|(suffix, expansion)| {
+            word.strip_suffix(suffix)
+                .map(|stem| format!("{}{expansion}", stem.replace('\'', "")))
+        })
+        .unwrap_or_else(|| word.replace('\'', "")),
+    };
+    Some(expanded)
+}
+
+/// Write out a number, like "1,234.5", as words.
+fn number_to_words(number: &str, ordinal: bool) -> String {
+    let (whole, fraction) = match number.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (number, None),
+    };
+    let whole = whole.replace(',', "");
+    let mut words = match whole.parse::<u64>() {
+        Ok(whole) => cardinal(whole),
+        Err(_) => digits(&whole),
+    };
+    match fraction {
+        Some(fraction) => {
+            words.push_str(" point ");
+            words.push_str(&digits(fraction));
+        }
+        None if ordinal => words = to_ordinal(&words),
+        None => {}
+    }
+    words
+}
+
+/// Write out each digit as a word, as for the digits after a decimal point.
+fn digits(number: &str) -> String {
+    number
+        .chars()
+        .filter_map(|digit| digit.to_digit(10))
+        .map(|digit| ONES[digit as usize])
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Write out a whole number as words, like "one hundred twenty three".
+fn cardinal(number: u64) -> String {
+    if number == 0 {
+        return ONES[0].to_string();
+    }
+    let mut words = vec![];
+    let mut remainder = number;
+    for (scale, name) in SCALES {
+        if remainder >= scale {
+            words.push(below_thousand(remainder / scale));
+            words.push(name.to_string());
+            remainder %= scale;
+        }
+    }
+    if remainder > 0 {
+        words.push(below_thousand(remainder));
+    }
+    words.join(" ")
+}
+
+fn below_thousand(number: u64) -> String {
+    let mut words = vec![];
+    let (hundreds, rest) = (number / 100, (number % 100) as usize);
+    if hundreds > 0 {
+        words.push(ONES[hundreds as usize]);
+        words.push("hundred");
+    }
+    if rest >= 20 {
+        words.push(TENS[rest / 10]);
+        if rest % 10 > 0 {
+            words.push(ONES[rest % 10]);
+        }
+    } else if rest > 0 {
+        words.push(ONES[rest]);
+    }
+    words.join(" ")
+}
+
+/// Turn a written-out cardinal number into an ordinal, like "twenty one" into "twenty first".
+fn to_ordinal(cardinal: &str) -> String {
+    let (rest, last) = match cardinal.rsplit_once(' ') {
+        Some((rest, last)) => (format!("{rest} "), last),
+        None => (String::new(), cardinal),
+    };
+    let last = match last {
+        "one" => "first".to_string(),
+        "two" => "second".to_string(),
+        "three" => "third".to_string(),
+        "five" => "fifth".to_string(),
+        "eight" => "eighth".to_string(),
+        "nine" => "ninth".to_string(),
+        "twelve" => "twelfth".to_string(),
+        last => match last.strip_suffix('y') {
+            Some(stem) => format!("{stem}ieth"),
+            None => format!("{last}th"),
+        },
+    };
+    rest + &last
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn punctuation() {
+        assert_eq!(
+            normalize("Hello there! General Kenobi."),
+            "hello there general kenobi"
+        );
+        assert_eq!(normalize("  well...well-well  "), "well well well");
+        assert_eq!(normalize("'quoted'"), "quoted");
+    }
+
+    #[test]
+    fn contractions() {
+        assert_eq!(normalize("I don't know"), "i do not know");
+        assert_eq!(normalize("I DON\u{2019}T know"), "i do not know");
+        assert_eq!(
+            normalize("won't, can't, cannot"),
+            "will not can not can not"
+        );
+        assert_eq!(
+            normalize("we're sure you'll see"),
+            "we are sure you will see"
+        );
+        assert_eq!(normalize("it's Bob's"), "its bobs");
+    }
+
+    #[test]
+    fn case_and_diacritics() {
+        assert_eq!(normalize("Café NAÏVE"), "cafe naive");
+        assert_eq!(normalize("Straße"), "strasse");
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(normalize("2 cats"), "two cats");
+        assert_eq!(normalize("0"), "zero");
+        assert_eq!(normalize("115"), "one hundred fifteen");
+        assert_eq!(normalize("1,000,021"), "one million twenty one");
+        assert_eq!(normalize("3.14"), "three point one four");
+        assert_eq!(
+            normalize("1st 2nd 3rd 12th 20th"),
+            "first second third twelfth twentieth"
+        );
+        assert_eq!(normalize("the 21st"), "the twenty first");
+        assert_eq!(normalize("99 bottles"), normalize("ninety-nine bottles"));
+        assert_eq!(normalize("at 5pm"), "at five pm");
+        // Only ASCII digits are numbers; other digits are dropped like punctuation.
+        assert_eq!(normalize("\u{0663} cats"), "cats");
+    }
+}
@@ -0,0 +1,106 @@
+// SPDX-License-Identifier: GPL-2.0-or-later
+//! Re-run speech-to-text on every clip.
+//!
+//! Each clip's detected speech comes from the Whisper model configured when it was added, so
+//! after switching models it's worth refreshing the whole library.
+use std::sync::{Arc, Mutex, PoisonError};
+
+use btfm_api_structs::RetranscribeStatus;
+use sqlx::SqlitePool;
+use tracing::{error, info, instrument};
+
+use crate::{db, transcribe::Transcriber};
+
+/// A handle to the progress of a re-transcription job; clones share the same progress.
+///
+/// Only one job can run at a time.
+#[derive(Clone, Default)]
+pub struct RetranscribeJob {
+    status: Arc<Mutex<RetranscribeStatus>>,
+}
+
+impl RetranscribeJob {
+    /// The progress of the current job, or the last job if none is running.
+    pub fn status(&self) -> RetranscribeStatus {
+        self.lock().clone()
+    }
+
+    /// Mark the job as running.
+    ///
+    /// # Returns
+    ///
+    /// `false` if a job was already running.
+    pub fn start(&self) -> bool {
+        let mut status = self.lock();
+        if status.running {
+            return false;
+        }
+        *status = RetranscribeStatus {
+            running: true,
+            started_on: Some(chrono::Utc::now().naive_utc()),
+            ..Default::default()
+        };
+        true
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, RetranscribeStatus> {
+        self.status.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn update(&self, f: impl FnOnce(&mut RetranscribeStatus)) {
+        f(&mut self.lock())
+    }
+}
+
+/// Re-run speech-to-text on every clip, recording progress in the job.
+///
+/// The job must already be started with [`RetranscribeJob::start`]; it is marked as finished
+/// when this returns. Clips are updated one at a time, so an interrupted job leaves a mix of old
+/// and new speech. Failing to transcribe a clip doesn't stop the job.
+///
+/// # Arguments
+///
+/// `skip_edited` - Leave clips whose detected speech was corrected by hand alone.
+#[instrument(skip(db_pool, transcriber, job))]
+pub async fn retranscribe(
+    db_pool: &SqlitePool,
+    transcriber: &Transcriber,
+    skip_edited: bool,
+    job: &RetranscribeJob,
+) -> Result<(), crate::Error> {
+    let result = async {
+        let clips = db::clips_list(&mut *db_pool.acquire().await?).await?;
+        job.update(|status| status.total = clips.len() as u64);
+
+        for clip in clips {
+            if skip_edited && clip.speech_detected_edited {
+                job.update(|status| status.skipped += 1);
+                continue;
+            }
+            let mut conn = db_pool.acquire().await?;
+            match db::retranscribe_clip(&mut conn, &clip, transcriber.clone()).await {
+                Ok(speech_detected) => {
+                    info!(
+                        clip = %clip.uuid,
+                        old = ?clip.speech_detected,
+                        new = ?speech_detected,
+                        "Re-transcribed clip"
+                    );
+                    job.update(|status| status.transcribed += 1);
+                }
+                Err(err) => {
+                    error!(clip = %clip.uuid, err = %err, "Failed to re-transcribe clip");
+                    job.update(|status| status.failed += 1);
+                }
+            }
+        }
+        Ok(())
+    }
+    .await;
+
+    job.update(|status| {
+        status.running = false;
+        status.finished_on = Some(chrono::Utc::now().naive_utc());
+    });
+    result
+}
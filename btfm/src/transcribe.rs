@@ -25,7 +25,8 @@ const WHISPER: &str = include_str!("transcribe.py");
 pub enum TranscriptionRequest {
     Stream {
         audio: mpsc::Receiver<bytes::Bytes>,
-        respond_to: oneshot::Sender<String>,
+        speaker: Option<u64>,
+        respond_to: oneshot::Sender<Transcript>,
         span: tracing::Span,
     },
     File {
@@ -35,6 +36,14 @@ pub enum TranscriptionRequest {
     Shutdown,
 }
 
+/// The text of a stream of audio, along with who said it.
+#[derive(Debug, Clone, Default)]
+pub struct Transcript {
+    pub text: String,
+    /// The Discord user ID of the speaker, if it's known.
+    pub speaker: Option<u64>,
+}
+
 #[derive(Debug, Clone)]
 pub struct Transcriber {
     sender: mpsc::Sender<TranscriptionRequest>,
@@ -57,12 +66,18 @@ impl Transcriber {
 
     /// Stream audio to the transcriber and receive a stream of text back
     ///
-    /// Audio is expected to be stereo signed 16 bit PCM at 48khz
-    pub async fn stream(&self, audio: mpsc::Receiver<bytes::Bytes>) -> oneshot::Receiver<String> {
+    /// Audio is expected to be stereo signed 16 bit PCM at 48khz. The speaker, if it's known, is
+    /// the Discord user ID the audio belongs to and is passed along with the text.
+    pub async fn stream(
+        &self,
+        audio: mpsc::Receiver<bytes::Bytes>,
+        speaker: Option<u64>,
+    ) -> oneshot::Receiver<Transcript> {
         let (respond_to, text_receiver) = oneshot::channel();
 
         let request = TranscriptionRequest::Stream {
             audio,
+            speaker,
             respond_to,
             span: tracing::Span::current(),
         };
@@ -174,6 +189,7 @@ impl TranscriberWorker {
             match request {
                 TranscriptionRequest::Stream {
                     audio,
+                    speaker,
                     respond_to,
                     span,
                 } => {
@@ -182,13 +198,17 @@ impl TranscriberWorker {
                         async move {
                             let bin = discord_to_whisper(audio).await.unwrap();
 
+                            let (text_sender, text_receiver) = oneshot::channel();
                             if transcriber
-                                .send(Request::Raw(bin, respond_to))
+                                .send(Request::Raw(bin, text_sender))
                                 .await
                                 .is_err()
                             {
                                 tracing::error!("The transcriber thread is gone?");
                             }
+                            if let Ok(text) = text_receiver.await {
+                                let _ = respond_to.send(Transcript { text, speaker });
+                            }
                         }
                         .instrument(span),
                     );
@@ -266,11 +286,12 @@ mod tests {
 
         let transcriber = Transcriber::new(&config).unwrap();
         let (tx, rx) = mpsc::channel(32);
-        let result = transcriber.stream(rx).await;
+        let result = transcriber.stream(rx, Some(1)).await;
         tx.send(BYTES).await.unwrap();
         drop(tx);
         let result = result.await.unwrap();
 
-        assert_eq!("I don't know how.".to_string(), result.trim());
+        assert_eq!("I don't know how.".to_string(), result.text.trim());
+        assert_eq!(Some(1), result.speaker);
     }
 }
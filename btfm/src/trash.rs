@@ -0,0 +1,46 @@
+// SPDX-License-Identifier: GPL-2.0-or-later
+//! Purge clips that have been in the trash longer than the configured age.
+//!
+//! Removed clips are moved to the trash so accidental removals can be undone; this keeps the
+//! trash from growing forever.
+use sqlx::SqlitePool;
+use tracing::{error, info, instrument};
+
+use crate::db;
+
+/// How often to check the trash for clips to purge.
+const PURGE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// Periodically purge clips older than `trash_purge_days` from the trash; this never returns.
+pub async fn purge_task(db_pool: SqlitePool) {
+    let mut interval = tokio::time::interval(PURGE_INTERVAL);
+    loop {
+        interval.tick().await;
+        if let Err(err) = purge(&db_pool).await {
+            error!("Failed to purge the trash: {}", err);
+        }
+    }
+}
+
+/// Purge clips older than `trash_purge_days` from the trash.
+///
+/// # Returns
+///
+/// The number of clips purged.
+#[instrument(skip_all)]
+pub async fn purge(db_pool: &SqlitePool) -> Result<usize, crate::Error> {
+    let config = crate::CONFIG.get().expect("Initialize the config");
+    let now = chrono::Utc::now().naive_utc();
+    let deleted_before = i64::try_from(config.trash_purge_days)
+        .ok()
+        .and_then(chrono::Duration::try_days)
+        .and_then(|max_age| now.checked_sub_signed(max_age))
+        .unwrap_or(chrono::NaiveDateTime::MIN);
+
+    let mut conn = db_pool.acquire().await?;
+    let purged = db::purge_trash(&mut conn, deleted_before).await?;
+    for clip in purged.iter() {
+        info!("Purged {} from the trash", clip);
+    }
+    Ok(purged.len())
+}
@@ -0,0 +1,52 @@
+use axum::{extract::Extension, http::StatusCode, Json};
+use sqlx::SqlitePool;
+use tracing::{error, instrument, Instrument};
+
+use crate::retranscribe::{retranscribe, RetranscribeJob};
+use crate::{matching::PhraseIndex, transcribe::Transcriber};
+
+use btfm_api_structs::{RetranscribeRequest, RetranscribeStatus};
+
+/// Start re-running speech-to-text on every clip in the background.
+///
+/// Only one job can run at a time; poll the GET endpoint for progress.
+#[instrument(skip(db_pool, transcriber, phrase_index, job))]
+pub async fn retranscribe_start(
+    Extension(db_pool): Extension<SqlitePool>,
+    Extension(transcriber): Extension<Transcriber>,
+    Extension(phrase_index): Extension<PhraseIndex>,
+    Extension(job): Extension<RetranscribeJob>,
+    Json(request): Json<RetranscribeRequest>,
+) -> Result<(StatusCode, Json<RetranscribeStatus>), crate::Error> {
+    if !job.start() {
+        return Err(crate::Error::Conflict);
+    }
+    let task_job = job.clone();
+    tokio::spawn(
+        async move {
+            if let Err(err) =
+                retranscribe(&db_pool, &transcriber, request.skip_edited, &task_job).await
+            {
+                error!(err = %err, "Re-transcription failed");
+            }
+            match db_pool.acquire().await {
+                Ok(mut conn) => {
+                    if let Err(err) = phrase_index.rebuild(&mut conn).await {
+                        error!(err = %err, "Failed to rebuild the phrase index");
+                    }
+                }
+                Err(err) => error!(err = %err, "Failed to rebuild the phrase index"),
+            }
+        }
+        .in_current_span(),
+    );
+    Ok((StatusCode::ACCEPTED, Json(job.status())))
+}
+
+/// Report the progress of the current re-transcription job, or the last one.
+#[instrument(skip(job))]
+pub async fn retranscribe_status(
+    Extension(job): Extension<RetranscribeJob>,
+) -> Json<RetranscribeStatus> {
+    Json(job.status())
+}
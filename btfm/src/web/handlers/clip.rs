@@ -1,32 +1,109 @@
 use axum::{
-    extract::{Extension, Multipart, Path},
+    extract::{Extension, Multipart, Path, Query},
     response::IntoResponse,
     Json,
 };
-use btfm_api_structs::{Clip, ClipUpdated, ClipUpload, Clips};
+use btfm_api_structs::{
+    Clip, ClipPatch, ClipQuery, ClipSearchQuery, ClipSearchResult, ClipSearchResults, ClipUpdated,
+    ClipUpload, Clips,
+};
 use sqlx::{types::Uuid, SqlitePool};
 use tokio_util::io::ReaderStream;
-use tracing::{info, instrument};
+use tracing::{error, info, instrument};
+
+use crate::web::serialization::{load_details, load_details_for_clips};
+use crate::{availability, db, matching::PhraseIndex, transcribe::Transcriber};
 
-use crate::web::serialization::load_phrases;
-use crate::{db, transcribe::Transcriber};
+/// The number of clips returned if no limit is requested.
+const DEFAULT_LIMIT: u32 = 100;
+/// The number of search results returned if no limit is requested.
+const DEFAULT_SEARCH_LIMIT: u32 = 20;
+/// The largest page of clips that can be requested.
+const MAX_LIMIT: u32 = 1000;
 
-/// List clips known to BTFM
+/// List clips known to BTFM, a page at a time.
+///
+/// The cursor is opaque to clients; it's the primary key and sort key of the last clip returned.
 #[instrument(skip(db_pool))]
 pub async fn get_all(
     Extension(db_pool): Extension<SqlitePool>,
+    Query(query): Query<ClipQuery>,
 ) -> Result<Json<Clips>, crate::Error> {
-    let mut clips = vec![];
-    let mut conn = db_pool.begin().await?;
-    for clip in db::clips_list(&mut conn).await? {
-        let mut conn = db_pool.acquire().await?;
-        let mut clip: Clip = clip.into();
-        load_phrases(&mut clip, &mut conn).await?;
-        clips.push(clip);
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        return Err(crate::Error::BadRequest);
     }
+    let after = query
+        .cursor
+        .as_deref()
+        .map(|cursor| cursor.split_once(':').ok_or(crate::Error::BadRequest))
+        .transpose()?;
+
+    let mut conn = db_pool.acquire().await?;
+    // Ask for one extra clip to find out whether there's another page.
+    let mut clips = db::clips_page(
+        &mut conn,
+        query.sort,
+        query.order,
+        query.enabled,
+        query.tag.as_deref(),
+        after,
+        i64::from(limit) + 1,
+    )
+    .await?;
+    let next_cursor = if clips.len() > limit as usize {
+        clips.truncate(limit as usize);
+        clips
+            .last()
+            .map(|clip| format!("{}:{}", clip.uuid, clip.sort_key(query.sort)))
+    } else {
+        None
+    };
+    let mut clips: Vec<Clip> = clips.into_iter().map(Into::into).collect();
+    load_details_for_clips(&mut clips, &mut conn).await?;
     Ok(Clips {
         items: clips.len() as u64,
         clips,
+        next_cursor,
+    }
+    .into())
+}
+
+/// Search clips, returning the best matches first.
+#[instrument(skip(db_pool))]
+pub async fn search(
+    Extension(db_pool): Extension<SqlitePool>,
+    Query(query): Query<ClipSearchQuery>,
+) -> Result<Json<ClipSearchResults>, crate::Error> {
+    let limit = query.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        return Err(crate::Error::BadRequest);
+    }
+    let mut conn = db_pool.acquire().await?;
+    let (mut clips, matches): (Vec<_>, Vec<_>) =
+        db::search_clips(&mut conn, &query.q, limit.into())
+            .await?
+            .into_iter()
+            .map(|search_match| {
+                (
+                    Clip::from(search_match.clip),
+                    (search_match.rank, search_match.snippet),
+                )
+            })
+            .unzip();
+    load_details_for_clips(&mut clips, &mut conn).await?;
+    let results = clips
+        .into_iter()
+        .zip(matches)
+        .map(|(clip, (rank, snippet))| ClipSearchResult {
+            clip,
+            rank,
+            snippet,
+        })
+        .collect::<Vec<_>>();
+    Ok(ClipSearchResults {
+        items: results.len() as u64,
+        results,
     }
     .into())
 }
@@ -40,7 +117,7 @@ pub async fn get(
     let uuid = uuid.to_string();
     let mut conn = db_pool.begin().await?;
     let mut clip: Clip = db::get_clip(&mut conn, uuid).await?.into();
-    load_phrases(&mut clip, &mut conn).await?;
+    load_details(&mut clip, &mut conn).await?;
     Ok(clip.into())
 }
 
@@ -81,10 +158,11 @@ pub async fn download_clip(
 /// This accepts a multipart form consisting of two parts. The first part is a JSON object
 /// with clip metadata and optional phrases to associate with it. The second part is the file
 /// itself.
-#[instrument(skip(db_pool, transcriber))]
+#[instrument(skip(db_pool, transcriber, phrase_index))]
 pub async fn create(
     Extension(db_pool): Extension<SqlitePool>,
     Extension(transcriber): Extension<Transcriber>,
+    Extension(phrase_index): Extension<PhraseIndex>,
     mut form: Multipart,
 ) -> Result<Json<Clip>, crate::Error> {
     let mut clip_metadata = None;
@@ -110,6 +188,12 @@ pub async fn create(
             }
         }
     }
+    if clip_metadata
+        .as_ref()
+        .is_some_and(|metadata| !valid_metadata(metadata))
+    {
+        return Err(crate::Error::BadRequest);
+    }
     match (clip_metadata, clip_data, filename) {
         (Some(metadata), Some(data), Some(filename)) => {
             let mut transaction = db_pool.begin().await?;
@@ -122,54 +206,204 @@ pub async fn create(
             )
             .await?
             .into();
-            load_phrases(&mut clip, &mut transaction).await?;
+            load_details(&mut clip, &mut transaction).await?;
             transaction.commit().await?;
+            phrase_index.rebuild(&mut *db_pool.acquire().await?).await?;
             Ok(clip.into())
         }
         _ => Err(crate::Error::BadRequest),
     }
 }
 
-#[instrument(skip(db_pool))]
+/// Replace a clip's audio and re-run speech-to-text on it.
+///
+/// This accepts a multipart form with a single part, "clip", containing the new audio file. The
+/// clip's ID, plays, and phrases are unchanged. The old audio file is removed once the change is
+/// committed.
+#[instrument(skip(db_pool, transcriber, phrase_index, form))]
+pub async fn replace_audio(
+    Extension(db_pool): Extension<SqlitePool>,
+    Extension(transcriber): Extension<Transcriber>,
+    Extension(phrase_index): Extension<PhraseIndex>,
+    Path(uuid): Path<Uuid>,
+    mut form: Multipart,
+) -> Result<Json<ClipUpdated>, crate::Error> {
+    let mut filename = None;
+    let mut clip_data = None;
+    while let Some(field) = form.next_field().await? {
+        match field.name().ok_or(crate::Error::BadRequest)? {
+            "clip" => {
+                filename = Some(
+                    field
+                        .file_name()
+                        .ok_or(crate::Error::BadRequest)?
+                        .to_owned(),
+                );
+                clip_data = Some(field.bytes().await?);
+            }
+            _ => {
+                info!(?field, "Ignoring unknown field");
+            }
+        }
+    }
+    let (Some(data), Some(filename)) = (clip_data, filename) else {
+        return Err(crate::Error::BadRequest);
+    };
+
+    let uuid = uuid.to_string();
+    // Speech-to-text is slow, so it's done before the transaction to avoid blocking other
+    // writers; the clip is checked first so the work isn't wasted on clips that don't exist.
+    db::get_clip(&mut *db_pool.acquire().await?, uuid.clone()).await?;
+    let audio = db::NewAudio::transcribe(data.to_vec(), &filename, transcriber).await?;
+
+    let result = async {
+        let mut transaction = db_pool.begin().await?;
+        let mut old_clip: Clip = db::get_clip(&mut transaction, uuid.clone()).await?.into();
+        load_details(&mut old_clip, &mut transaction).await?;
+        let mut new_clip: Clip = db::replace_audio(&mut transaction, uuid, &audio)
+            .await?
+            .into();
+        load_details(&mut new_clip, &mut transaction).await?;
+        transaction.commit().await?;
+        Ok::<_, crate::Error>((old_clip, new_clip))
+    }
+    .await;
+    let (old_clip, new_clip) = match result {
+        Ok(clips) => clips,
+        Err(err) => {
+            db::remove_audio_file(&audio.audio_file).await;
+            return Err(err);
+        }
+    };
+    db::remove_audio_file(&old_clip.audio_file).await;
+    phrase_index.rebuild(&mut *db_pool.acquire().await?).await?;
+
+    Ok(ClipUpdated { old_clip, new_clip }.into())
+}
+
+/// Replace a clip's phrases and update its metadata.
+///
+/// Empty titles and descriptions are left unchanged, as are any optional fields that aren't
+/// provided. Phrases the clip already has keep their settings, phrases missing from the list are
+/// unlinked from the clip, and new phrases are added as literal phrases. Use [`patch`] to add or
+/// remove individual phrases.
+#[instrument(skip(db_pool, phrase_index))]
 pub async fn edit(
     Extension(db_pool): Extension<SqlitePool>,
+    Extension(phrase_index): Extension<PhraseIndex>,
     Path(uuid): Path<Uuid>,
     Json(clip_metadata): Json<ClipUpload>,
+) -> Result<Json<ClipUpdated>, crate::Error> {
+    if !valid_metadata(&clip_metadata) {
+        return Err(crate::Error::BadRequest);
+    }
+    let clip_patch = ClipPatch {
+        title: Some(clip_metadata.title).filter(|title| !title.is_empty()),
+        description: Some(clip_metadata.description).filter(|desc| !desc.is_empty()),
+        add_phrases: vec![],
+        remove_phrases: vec![],
+        weight: clip_metadata.weight,
+        cooldown: clip_metadata.cooldown.map(Some),
+        speech_detected: clip_metadata.speech_detected,
+        match_speech_detected: clip_metadata.match_speech_detected,
+        enabled: None,
+        availability: None,
+    };
+    apply_patch(
+        &db_pool,
+        &phrase_index,
+        uuid,
+        &clip_patch,
+        clip_metadata.phrases.as_deref(),
+    )
+    .await
+}
+
+/// Update some of a clip's metadata, adding and removing individual phrases.
+#[instrument(skip(db_pool, phrase_index))]
+pub async fn patch(
+    Extension(db_pool): Extension<SqlitePool>,
+    Extension(phrase_index): Extension<PhraseIndex>,
+    Path(uuid): Path<Uuid>,
+    Json(clip_patch): Json<ClipPatch>,
+) -> Result<Json<ClipUpdated>, crate::Error> {
+    if !valid_patch(&clip_patch) {
+        return Err(crate::Error::BadRequest);
+    }
+    apply_patch(&db_pool, &phrase_index, uuid, &clip_patch, None).await
+}
+
+/// Apply the patch to the clip and, if `phrases` is set, make them the clip's only phrases.
+async fn apply_patch(
+    db_pool: &SqlitePool,
+    phrase_index: &PhraseIndex,
+    uuid: Uuid,
+    clip_patch: &ClipPatch,
+    phrases: Option<&[String]>,
 ) -> Result<Json<ClipUpdated>, crate::Error> {
     let uuid = uuid.to_string();
     let mut transaction = db_pool.begin().await?;
 
     let mut old_clip: Clip = db::get_clip(&mut transaction, uuid.clone()).await?.into();
-    load_phrases(&mut old_clip, &mut transaction).await?;
+    load_details(&mut old_clip, &mut transaction).await?;
 
-    let description = match clip_metadata.description.is_empty() {
-        true => &old_clip.description,
-        false => &clip_metadata.description,
-    };
-
-    db::update_clip(
-        &mut transaction,
-        uuid.clone(),
-        description,
-        &clip_metadata.phrases.unwrap_or_default(),
-    )
-    .await?;
+    db::update_clip(&mut transaction, uuid.clone(), clip_patch).await?;
+    if let Some(phrases) = phrases {
+        db::replace_phrases(&mut transaction, &uuid, phrases).await?;
+    }
 
     let mut new_clip: Clip = db::get_clip(&mut transaction, uuid).await?.into();
-    load_phrases(&mut new_clip, &mut transaction).await?;
+    load_details(&mut new_clip, &mut transaction).await?;
 
     transaction.commit().await?;
+    phrase_index.rebuild(&mut *db_pool.acquire().await?).await?;
     Ok(ClipUpdated { old_clip, new_clip }.into())
 }
 
-#[instrument(skip(db_pool))]
+#[instrument(skip(db_pool, phrase_index))]
 pub async fn delete(
     Extension(db_pool): Extension<SqlitePool>,
+    Extension(phrase_index): Extension<PhraseIndex>,
     Path(uuid): Path<Uuid>,
 ) -> Result<Json<Clip>, crate::Error> {
     let uuid = uuid.to_string();
     let mut transaction = db_pool.begin().await?;
-    let clip: Clip = db::remove_clip(&mut transaction, uuid).await?.into();
+    let (clip, audio_move) = db::remove_clip(&mut transaction, uuid).await?;
     transaction.commit().await?;
-    Ok(clip.into())
+    if let Err(err) = audio_move.apply().await {
+        // The audio is still with the other clips, so take the clip back out of the trash.
+        error!(err = %err, "Failed to move the audio file to the trash");
+        db::revert_trash_change(&mut *db_pool.acquire().await?, &clip).await?;
+        return Err(err);
+    }
+    phrase_index.rebuild(&mut *db_pool.acquire().await?).await?;
+    Ok(Clip::from(clip).into())
+}
+
+/// Weights must be finite and non-negative, and cooldowns must be non-negative and no longer
+/// than [`db::MAX_COOLDOWN`].
+fn valid_metadata(metadata: &ClipUpload) -> bool {
+    valid_weight(metadata.weight) && valid_cooldown(metadata.cooldown)
+}
+
+/// In addition to the rules for [`valid_metadata`], titles can't be changed to be empty and
+/// availability windows must be valid.
+fn valid_patch(patch: &ClipPatch) -> bool {
+    valid_weight(patch.weight)
+        && valid_cooldown(patch.cooldown.flatten())
+        && patch.title.as_ref().is_none_or(|title| !title.is_empty())
+        && patch
+            .availability
+            .as_ref()
+            .is_none_or(|windows| windows.iter().all(availability::is_valid))
+}
+
+fn valid_weight(weight: Option<f64>) -> bool {
+    weight.is_none_or(|weight| weight.is_finite() && weight >= 0.0)
+}
+
+fn valid_cooldown(cooldown: Option<i64>) -> bool {
+    cooldown.is_none_or(|cooldown| {
+        u64::try_from(cooldown).is_ok_and(|cooldown| cooldown <= db::MAX_COOLDOWN)
+    })
 }
@@ -0,0 +1,82 @@
+use axum::{
+    extract::{Extension, Path},
+    Json,
+};
+use sqlx::{types::Uuid, SqlitePool};
+use tracing::instrument;
+
+use crate::web::serialization::db_exclusions_to_api;
+use crate::{db, matching::PhraseIndex};
+
+use btfm_api_structs::{CreateExclusion, Exclusion, Exclusions};
+
+/// Show the exclusion phrase with the given ID.
+#[instrument(skip(db_pool))]
+pub async fn get(
+    Extension(db_pool): Extension<SqlitePool>,
+    Path(uuid): Path<Uuid>,
+) -> Result<Json<Exclusion>, crate::Error> {
+    let mut conn = db_pool.acquire().await?;
+    let exclusion: Exclusion = db::get_exclusion(&mut conn, uuid.to_string()).await?.into();
+    Ok(exclusion.into())
+}
+
+/// List all exclusion phrases.
+#[instrument(skip(db_pool))]
+pub async fn get_all(
+    Extension(db_pool): Extension<SqlitePool>,
+) -> Result<Json<Exclusions>, crate::Error> {
+    let mut conn = db_pool.acquire().await?;
+    let exclusions = db::list_exclusions(&mut conn, None).await?;
+    Ok(db_exclusions_to_api(exclusions).into())
+}
+
+/// Get all exclusion phrases for a given clip.
+#[instrument(skip(db_pool))]
+pub async fn by_clip(
+    Extension(db_pool): Extension<SqlitePool>,
+    Path(clip_uuid): Path<Uuid>,
+) -> Result<Json<Exclusions>, crate::Error> {
+    let mut conn = db_pool.acquire().await?;
+    let exclusions = db::list_exclusions(&mut conn, Some(clip_uuid.to_string())).await?;
+    Ok(db_exclusions_to_api(exclusions).into())
+}
+
+/// Add a phrase that stops a clip from playing.
+#[instrument(skip(db_pool, phrase_index))]
+pub async fn create(
+    Extension(db_pool): Extension<SqlitePool>,
+    Extension(phrase_index): Extension<PhraseIndex>,
+    Json(exclusion_upload): Json<CreateExclusion>,
+) -> Result<Json<Exclusion>, crate::Error> {
+    if exclusion_upload.phrase.trim().is_empty() {
+        return Err(crate::Error::BadRequest);
+    }
+    let mut transaction = db_pool.begin().await?;
+    let exclusion: Exclusion = db::add_exclusion(
+        &mut transaction,
+        exclusion_upload.clip,
+        &exclusion_upload.phrase,
+    )
+    .await?
+    .into();
+    transaction.commit().await?;
+    phrase_index.rebuild(&mut *db_pool.acquire().await?).await?;
+    Ok(exclusion.into())
+}
+
+/// Remove an exclusion phrase.
+#[instrument(skip(db_pool, phrase_index))]
+pub async fn delete(
+    Extension(db_pool): Extension<SqlitePool>,
+    Extension(phrase_index): Extension<PhraseIndex>,
+    Path(uuid): Path<Uuid>,
+) -> Result<Json<Exclusion>, crate::Error> {
+    let mut transaction = db_pool.begin().await?;
+    let exclusion: Exclusion = db::remove_exclusion(&mut transaction, uuid.to_string())
+        .await?
+        .into();
+    transaction.commit().await?;
+    phrase_index.rebuild(&mut *db_pool.acquire().await?).await?;
+    Ok(exclusion.into())
+}
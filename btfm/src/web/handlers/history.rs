@@ -0,0 +1,39 @@
+use axum::{
+    extract::{Extension, Query},
+    Json,
+};
+use sqlx::SqlitePool;
+use tracing::instrument;
+
+use crate::db;
+
+use btfm_api_structs::{HistoryQuery, Plays};
+
+/// The largest page of history that can be requested.
+const MAX_LIMIT: i64 = 500;
+
+/// List clips that have been played, most recent first.
+#[instrument(skip(db_pool))]
+pub async fn get_all(
+    Extension(db_pool): Extension<SqlitePool>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<Plays>, crate::Error> {
+    let limit = query.limit.map_or(50, i64::from);
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        return Err(crate::Error::BadRequest);
+    }
+    let mut conn = db_pool.acquire().await?;
+    let (plays, total) = db::list_plays(
+        &mut conn,
+        query.clip.map(|uuid| uuid.to_string()),
+        limit,
+        query.offset.map_or(0, i64::from),
+    )
+    .await?;
+    Ok(Plays {
+        items: plays.len() as u64,
+        total: total as u64,
+        plays: plays.into_iter().map(Into::into).collect(),
+    }
+    .into())
+}
@@ -0,0 +1,57 @@
+use axum::{extract::Extension, Json};
+use sqlx::SqlitePool;
+use tracing::instrument;
+
+use crate::discord::voice::{bypasses_rate_limit, play_chance};
+use crate::web::serialization::load_details_for_clips;
+use crate::{db, matching::PhraseIndex};
+
+use btfm_api_structs::{Clip, MatchCandidate, MatchRequest, MatchResult};
+
+/// Match text against clips the same way the bot matches speech, without playing anything.
+///
+/// This reports every clip that matched and why, along with the current odds of playing a clip.
+#[instrument(skip(db_pool, phrase_index))]
+pub async fn create(
+    Extension(db_pool): Extension<SqlitePool>,
+    Extension(phrase_index): Extension<PhraseIndex>,
+    Json(request): Json<MatchRequest>,
+) -> Result<Json<MatchResult>, crate::Error> {
+    let config = crate::CONFIG.get().expect("Initialize the config");
+    let normalized_text = crate::normalize::normalize(&request.text);
+
+    let mut conn = db_pool.acquire().await?;
+    let since_last_play = chrono::Utc::now().naive_utc() - db::last_play_time(&mut conn).await;
+    let matches = if normalized_text.trim().is_empty() {
+        vec![]
+    } else {
+        db::find_matches(&mut conn, &phrase_index, &request.text, request.speaker).await?
+    };
+
+    let mut clips: Vec<Clip> = matches
+        .iter()
+        .map(|clip_match| clip_match.clip.clone().into())
+        .collect();
+    load_details_for_clips(&mut clips, &mut conn).await?;
+    let candidates = clips
+        .into_iter()
+        .zip(matches)
+        .map(|(clip, clip_match)| MatchCandidate {
+            clip,
+            phrase: clip_match.phrase,
+            score: clip_match.score,
+            cooling_down: clip_match.cooling_down,
+            excluded_by: clip_match.excluded_by,
+            muted: clip_match.muted,
+            unavailable: clip_match.unavailable,
+        })
+        .collect();
+
+    Ok(MatchResult {
+        play_chance: play_chance(since_last_play, config.rate_adjuster),
+        bypasses_rate_limit: bypasses_rate_limit(&normalized_text),
+        normalized_text,
+        candidates,
+    }
+    .into())
+}
@@ -1,3 +1,10 @@
+pub(crate) mod admin;
 pub(crate) mod clip;
+pub(crate) mod exclusion;
+pub(crate) mod history;
+pub(crate) mod matching;
 pub(crate) mod phrase;
+pub(crate) mod stats;
 pub(crate) mod status;
+pub(crate) mod tag;
+pub(crate) mod trash;
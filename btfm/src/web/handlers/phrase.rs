@@ -1,14 +1,17 @@
 use axum::{
-    extract::{Extension, Path},
+    extract::{Extension, Path, Query},
     Json,
 };
 use sqlx::{types::Uuid, SqlitePool};
 use tracing::instrument;
 
-use crate::db;
 use crate::web::serialization::db_phrases_to_api;
+use crate::{db, matching, matching::PhraseIndex};
 
-use btfm_api_structs::{CreatePhrase, Phrase, Phrases};
+use btfm_api_structs::{ConflictsQuery, CreatePhrase, Phrase, PhraseConflicts, Phrases};
+
+/// Phrases shorter than this are reported as conflicts if no minimum length is given.
+const DEFAULT_MIN_LENGTH: usize = 4;
 
 /// Show the phrase associated with a given Ulid.
 #[instrument(skip(db_pool))]
@@ -32,6 +35,40 @@ pub async fn get_all(
     Ok(phrases.into())
 }
 
+/// Report phrases that duplicate or overlap other phrases, phrases short enough to match
+/// constantly, and phrases that appear in other clips' detected speech.
+#[instrument(skip(db_pool))]
+pub async fn conflicts(
+    Extension(db_pool): Extension<SqlitePool>,
+    Query(query): Query<ConflictsQuery>,
+) -> Result<Json<PhraseConflicts>, crate::Error> {
+    let config = crate::CONFIG.get().expect("Initialize the config");
+    let min_words = config.matching.speech_detected_min_words;
+    let mut conn = db_pool.begin().await?;
+    let phrases = db::list_phrases(&mut conn).await?;
+    // Only the speech the phrase index matches on is considered.
+    let speech = db::clips_list(&mut conn)
+        .await?
+        .into_iter()
+        .filter(|clip| clip.enabled && clip.match_speech_detected)
+        .filter_map(|clip| {
+            clip.speech_detected
+                .filter(|speech| speech.split_whitespace().count() >= min_words)
+                .map(|speech| (clip.uuid, speech))
+        })
+        .collect::<Vec<_>>();
+    let conflicts = matching::find_conflicts(
+        &phrases,
+        &speech,
+        query.min_length.unwrap_or(DEFAULT_MIN_LENGTH),
+    );
+    Ok(PhraseConflicts {
+        items: conflicts.len() as u64,
+        conflicts,
+    }
+    .into())
+}
+
 /// Get all phrases for a given clip.
 #[instrument(skip(db_pool))]
 pub async fn by_clip(
@@ -44,28 +81,93 @@ pub async fn by_clip(
     Ok(phrases.into())
 }
 
-/// Create a new trigger phrase for a clip.
-#[instrument(skip(db_pool))]
+/// Create a new trigger phrase for some clips, or link the clips to an identical phrase with
+/// the same speakers.
+///
+/// Regular expression phrases are validated and a 400 is returned if they don't compile.
+#[instrument(skip(db_pool, phrase_index))]
 pub async fn create(
     Extension(db_pool): Extension<SqlitePool>,
+    Extension(phrase_index): Extension<PhraseIndex>,
     Json(phrase_upload): Json<CreatePhrase>,
 ) -> Result<Json<Phrase>, crate::Error> {
-    let clip_uuid = phrase_upload.clip.to_string();
-    let mut conn = db_pool.begin().await?;
-    let phrase: Phrase = db::add_phrase(&mut conn, clip_uuid, &phrase_upload.phrase)
+    if !valid_threshold(phrase_upload.similarity_threshold) {
+        return Err(crate::Error::BadRequest);
+    }
+    let mut transaction = db_pool.begin().await?;
+    let phrase: Phrase = db::add_phrase(
+        &mut transaction,
+        &phrase_upload.clips,
+        &phrase_upload.phrase,
+        phrase_upload.similarity_threshold,
+        phrase_upload.substring,
+        phrase_upload.kind.into(),
+        &speakers(&phrase_upload),
+    )
+    .await?
+    .into();
+    transaction.commit().await?;
+    phrase_index.rebuild(&mut *db_pool.acquire().await?).await?;
+    Ok(phrase.into())
+}
+
+/// Replace a phrase's text and settings, or change the clips it's linked to.
+///
+/// Like [`create`], a 400 is returned if the phrase is an invalid regular expression.
+#[instrument(skip(db_pool, phrase_index))]
+pub async fn update(
+    Extension(db_pool): Extension<SqlitePool>,
+    Extension(phrase_index): Extension<PhraseIndex>,
+    Path(uuid): Path<Uuid>,
+    Json(phrase_upload): Json<CreatePhrase>,
+) -> Result<Json<Phrase>, crate::Error> {
+    if !valid_threshold(phrase_upload.similarity_threshold) {
+        return Err(crate::Error::BadRequest);
+    }
+    let uuid = uuid.to_string();
+    let mut transaction = db_pool.begin().await?;
+    db::update_phrase(
+        &mut transaction,
+        uuid.clone(),
+        &phrase_upload.clips,
+        &phrase_upload.phrase,
+        phrase_upload.similarity_threshold,
+        phrase_upload.substring,
+        phrase_upload.kind.into(),
+    )
+    .await?;
+    let phrase: Phrase = db::set_phrase_speakers(&mut transaction, uuid, &speakers(&phrase_upload))
         .await?
         .into();
+    transaction.commit().await?;
+    phrase_index.rebuild(&mut *db_pool.acquire().await?).await?;
     Ok(phrase.into())
 }
 
-/// Show the phrase associated with a given Ulid.
-#[instrument(skip(db_pool))]
+/// Remove a phrase, unlinking it from all of its clips.
+#[instrument(skip(db_pool, phrase_index))]
 pub async fn delete(
     Extension(db_pool): Extension<SqlitePool>,
+    Extension(phrase_index): Extension<PhraseIndex>,
     Path(uuid): Path<Uuid>,
 ) -> Result<Json<Phrase>, crate::Error> {
     let uuid = uuid.to_string();
-    let mut conn = db_pool.begin().await?;
-    let phrase: Phrase = db::remove_phrase(&mut conn, uuid).await?.into();
+    let mut transaction = db_pool.begin().await?;
+    let phrase: Phrase = db::remove_phrase(&mut transaction, uuid).await?.into();
+    transaction.commit().await?;
+    phrase_index.rebuild(&mut *db_pool.acquire().await?).await?;
     Ok(phrase.into())
 }
+
+/// Similarity thresholds must be between 0 and 1.
+fn valid_threshold(similarity_threshold: Option<f64>) -> bool {
+    similarity_threshold.is_none_or(|threshold| (0.0..=1.0).contains(&threshold))
+}
+
+/// The users who can, or can't, trigger the uploaded phrase.
+fn speakers(phrase: &CreatePhrase) -> db::Speakers {
+    db::Speakers {
+        allowed: phrase.allowed_speakers.clone(),
+        denied: phrase.denied_speakers.clone(),
+    }
+}
@@ -0,0 +1,51 @@
+use axum::{
+    extract::{Extension, Query},
+    Json,
+};
+use chrono::{Days, NaiveDate};
+use sqlx::SqlitePool;
+use tracing::instrument;
+
+use crate::db;
+
+use btfm_api_structs::{Stats, StatsQuery};
+
+/// The number of days included in the statistics if no start day is given.
+const DEFAULT_DAYS: u64 = 30;
+
+/// Summarize clip plays over a range of days.
+#[instrument(skip(db_pool))]
+pub async fn get(
+    Extension(db_pool): Extension<SqlitePool>,
+    Query(query): Query<StatsQuery>,
+) -> Result<Json<Stats>, crate::Error> {
+    let until = query
+        .until
+        .unwrap_or_else(|| chrono::Utc::now().date_naive());
+    let since = query
+        .since
+        .or_else(|| until.checked_sub_days(Days::new(DEFAULT_DAYS)))
+        .unwrap_or(NaiveDate::MIN);
+    if since > until {
+        return Err(crate::Error::BadRequest);
+    }
+    let limit = query.limit.unwrap_or(10).into();
+
+    // The range covers all of the last day.
+    let start = since.and_time(chrono::NaiveTime::MIN);
+    let end = until
+        .succ_opt()
+        .unwrap_or(NaiveDate::MAX)
+        .and_time(chrono::NaiveTime::MIN);
+
+    let mut conn = db_pool.acquire().await?;
+    Ok(Stats {
+        since,
+        until,
+        top_clips: db::top_clips(&mut conn, start, end, limit).await?,
+        plays_per_day: db::plays_per_day(&mut conn, start, end).await?,
+        never_played: db::never_played(&mut conn).await?,
+        top_phrases: db::top_phrases(&mut conn, start, end, limit).await?,
+    }
+    .into())
+}
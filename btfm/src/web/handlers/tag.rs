@@ -0,0 +1,105 @@
+use axum::{
+    extract::{Extension, Path},
+    Json,
+};
+use sqlx::{types::Uuid, SqlitePool};
+use tracing::instrument;
+
+use crate::db;
+use crate::web::serialization::db_tags_to_api;
+
+use btfm_api_structs::{CreateTag, Tag, Tags};
+
+/// Show the tag with the given ID.
+#[instrument(skip(db_pool))]
+pub async fn get(
+    Extension(db_pool): Extension<SqlitePool>,
+    Path(uuid): Path<Uuid>,
+) -> Result<Json<Tag>, crate::Error> {
+    let mut conn = db_pool.acquire().await?;
+    let tag: Tag = db::get_tag(&mut conn, uuid.to_string()).await?.into();
+    Ok(tag.into())
+}
+
+/// List all tags.
+#[instrument(skip(db_pool))]
+pub async fn get_all(
+    Extension(db_pool): Extension<SqlitePool>,
+) -> Result<Json<Tags>, crate::Error> {
+    let mut conn = db_pool.acquire().await?;
+    let tags = db::list_tags(&mut conn).await?;
+    Ok(db_tags_to_api(tags).into())
+}
+
+/// Add a new tag.
+#[instrument(skip(db_pool))]
+pub async fn create(
+    Extension(db_pool): Extension<SqlitePool>,
+    Json(tag_upload): Json<CreateTag>,
+) -> Result<Json<Tag>, crate::Error> {
+    let mut transaction = db_pool.begin().await?;
+    let tag: Tag = db::add_tag(&mut transaction, &tag_upload.name)
+        .await?
+        .into();
+    transaction.commit().await?;
+    Ok(tag.into())
+}
+
+/// Rename a tag.
+#[instrument(skip(db_pool))]
+pub async fn update(
+    Extension(db_pool): Extension<SqlitePool>,
+    Path(uuid): Path<Uuid>,
+    Json(tag_upload): Json<CreateTag>,
+) -> Result<Json<Tag>, crate::Error> {
+    let mut transaction = db_pool.begin().await?;
+    let tag: Tag = db::rename_tag(&mut transaction, uuid.to_string(), &tag_upload.name)
+        .await?
+        .into();
+    transaction.commit().await?;
+    Ok(tag.into())
+}
+
+/// Remove a tag from all its clips and delete it.
+#[instrument(skip(db_pool))]
+pub async fn delete(
+    Extension(db_pool): Extension<SqlitePool>,
+    Path(uuid): Path<Uuid>,
+) -> Result<Json<Tag>, crate::Error> {
+    let mut transaction = db_pool.begin().await?;
+    let tag: Tag = db::remove_tag(&mut transaction, uuid.to_string())
+        .await?
+        .into();
+    transaction.commit().await?;
+    Ok(tag.into())
+}
+
+/// Tag a clip, creating the tag if it doesn't exist yet.
+#[instrument(skip(db_pool))]
+pub async fn tag_clip(
+    Extension(db_pool): Extension<SqlitePool>,
+    Path(clip_uuid): Path<Uuid>,
+    Json(tag_upload): Json<CreateTag>,
+) -> Result<Json<Tag>, crate::Error> {
+    let mut transaction = db_pool.begin().await?;
+    let clip = db::get_clip(&mut transaction, clip_uuid.to_string()).await?;
+    let tag: Tag = db::tag_clip(&mut transaction, clip.uuid, &tag_upload.name)
+        .await?
+        .into();
+    transaction.commit().await?;
+    Ok(tag.into())
+}
+
+/// Remove a tag, by name, from a clip.
+#[instrument(skip(db_pool))]
+pub async fn untag_clip(
+    Extension(db_pool): Extension<SqlitePool>,
+    Path((clip_uuid, name)): Path<(Uuid, String)>,
+) -> Result<Json<Tag>, crate::Error> {
+    let mut transaction = db_pool.begin().await?;
+    let tag: Tag = db::untag_clip(&mut transaction, clip_uuid.to_string(), &name)
+        .await?
+        .into();
+    transaction.commit().await?;
+    Ok(tag.into())
+}
@@ -0,0 +1,53 @@
+use axum::{
+    extract::{Extension, Path},
+    Json,
+};
+use btfm_api_structs::{Clip, Clips};
+use sqlx::{types::Uuid, SqlitePool};
+use tracing::{error, instrument};
+
+use crate::web::serialization::{load_details, load_details_for_clips};
+use crate::{db, matching::PhraseIndex};
+
+/// List the clips in the trash, most recently removed first.
+#[instrument(skip(db_pool))]
+pub async fn get_all(
+    Extension(db_pool): Extension<SqlitePool>,
+) -> Result<Json<Clips>, crate::Error> {
+    let mut conn = db_pool.acquire().await?;
+    let mut clips: Vec<Clip> = db::list_trash(&mut conn)
+        .await?
+        .into_iter()
+        .map(Into::into)
+        .collect();
+    load_details_for_clips(&mut clips, &mut conn).await?;
+    Ok(Clips {
+        items: clips.len() as u64,
+        clips,
+        next_cursor: None,
+    }
+    .into())
+}
+
+/// Take a clip out of the trash; it keeps the phrases it had when it was removed.
+#[instrument(skip(db_pool, phrase_index))]
+pub async fn restore(
+    Extension(db_pool): Extension<SqlitePool>,
+    Extension(phrase_index): Extension<PhraseIndex>,
+    Path(uuid): Path<Uuid>,
+) -> Result<Json<Clip>, crate::Error> {
+    let uuid = uuid.to_string();
+    let mut transaction = db_pool.begin().await?;
+    let (trashed, audio_move) = db::restore_clip(&mut transaction, uuid.clone()).await?;
+    let mut clip: Clip = db::get_clip(&mut transaction, uuid).await?.into();
+    load_details(&mut clip, &mut transaction).await?;
+    transaction.commit().await?;
+    if let Err(err) = audio_move.apply().await {
+        // The audio is still in the trash, so put the clip back there too.
+        error!(err = %err, "Failed to move the audio file out of the trash");
+        db::revert_trash_change(&mut *db_pool.acquire().await?, &trashed).await?;
+        return Err(err);
+    }
+    phrase_index.rebuild(&mut *db_pool.acquire().await?).await?;
+    Ok(clip.into())
+}
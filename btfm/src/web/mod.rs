@@ -3,7 +3,7 @@ use axum::{
     extract::Extension,
     http::{Request, Response, StatusCode},
     response::IntoResponse,
-    routing::get,
+    routing::{delete, get, post},
     Json, Router,
 };
 use hyper::header;
@@ -21,7 +21,10 @@ use tower_http::{
 use tracing::Level;
 use uuid::Uuid;
 
-use crate::{config::HttpApi, transcribe::Transcriber, Error};
+use crate::{
+    config::HttpApi, matching::PhraseIndex, retranscribe::RetranscribeJob, transcribe::Transcriber,
+    Error,
+};
 
 pub(crate) mod handlers;
 pub(crate) mod serialization;
@@ -39,32 +42,83 @@ impl MakeRequestId for MakeRequestUlid {
 }
 
 /// Create an Axum router configured with middleware.
-pub fn create_router(config: &HttpApi, db: SqlitePool, transcriber: Transcriber) -> Router {
+pub fn create_router(
+    config: &HttpApi,
+    db: SqlitePool,
+    transcriber: Transcriber,
+    phrase_index: PhraseIndex,
+) -> Router {
     let app = Router::new()
         .route("/status/", get(handlers::status::get))
+        .route("/v1/clips/search", get(handlers::clip::search))
         .route("/v1/clips/{uuid}/phrases/", get(handlers::phrase::by_clip))
+        .route(
+            "/v1/clips/{uuid}/exclusions/",
+            get(handlers::exclusion::by_clip),
+        )
+        .route("/v1/clips/{uuid}/tags/", post(handlers::tag::tag_clip))
+        .route(
+            "/v1/clips/{uuid}/tags/{name}",
+            delete(handlers::tag::untag_clip),
+        )
         .route(
             "/v1/clips/{uuid}",
             get(handlers::clip::get)
                 .delete(handlers::clip::delete)
-                .put(handlers::clip::edit),
+                .put(handlers::clip::edit)
+                .patch(handlers::clip::patch),
+        )
+        .route(
+            "/v1/clips/{uuid}/audio",
+            get(handlers::clip::download_clip).put(handlers::clip::replace_audio),
         )
-        .route("/v1/clips/{uuid}/audio", get(handlers::clip::download_clip))
         .route(
             "/v1/clips/",
             get(handlers::clip::get_all).post(handlers::clip::create),
         )
+        .route("/v1/phrases/conflicts", get(handlers::phrase::conflicts))
         .route(
             "/v1/phrases/{uuid}",
-            get(handlers::phrase::get).delete(handlers::phrase::delete),
+            get(handlers::phrase::get)
+                .delete(handlers::phrase::delete)
+                .put(handlers::phrase::update),
         )
         .route(
             "/v1/phrases/",
             get(handlers::phrase::get_all).post(handlers::phrase::create),
         )
+        .route(
+            "/v1/exclusions/{uuid}",
+            get(handlers::exclusion::get).delete(handlers::exclusion::delete),
+        )
+        .route(
+            "/v1/exclusions/",
+            get(handlers::exclusion::get_all).post(handlers::exclusion::create),
+        )
+        .route(
+            "/v1/tags/{uuid}",
+            get(handlers::tag::get)
+                .delete(handlers::tag::delete)
+                .put(handlers::tag::update),
+        )
+        .route(
+            "/v1/tags/",
+            get(handlers::tag::get_all).post(handlers::tag::create),
+        )
+        .route(
+            "/v1/admin/retranscribe",
+            get(handlers::admin::retranscribe_status).post(handlers::admin::retranscribe_start),
+        )
+        .route("/v1/history", get(handlers::history::get_all))
+        .route("/v1/trash", get(handlers::trash::get_all))
+        .route("/v1/trash/{uuid}/restore", post(handlers::trash::restore))
+        .route("/v1/match", post(handlers::matching::create))
+        .route("/v1/stats", get(handlers::stats::get))
         .fallback(handle_404)
         .layer(Extension(db))
-        .layer(Extension(transcriber));
+        .layer(Extension(transcriber))
+        .layer(Extension(phrase_index))
+        .layer(Extension(RetranscribeJob::default()));
 
     // Ordering matters here; requests pass through middleware top-to-bottom and responses bottom-to-top
     let middleware = ServiceBuilder::new()
@@ -96,9 +150,16 @@ impl IntoResponse for Error {
         let (status, error_message) = match self {
             Error::Database(_) => (
                 StatusCode::SERVICE_UNAVAILABLE,
-                "The database is unavailable",
+                "The database is unavailable".to_string(),
+            ),
+            Error::BadRequest => (StatusCode::BAD_REQUEST, self.to_string()),
+            Error::InvalidRegex(_) => (StatusCode::BAD_REQUEST, self.to_string()),
+            Error::Conflict => (StatusCode::CONFLICT, self.to_string()),
+            Error::NotFound => (StatusCode::NOT_FOUND, self.to_string()),
+            _ => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Something went oopsies".to_string(),
             ),
-            _ => (StatusCode::INTERNAL_SERVER_ERROR, "Something went oopsies"),
         };
 
         let body = Json(json!({
@@ -1,15 +1,56 @@
 /// Defines public-facing structures used in the web API
+use std::collections::HashMap;
+
 use sqlx::SqliteConnection;
 
 use crate::db;
-use btfm_api_structs::{Clip, Phrase, Phrases};
+use btfm_api_structs::{Clip, Exclusion, Exclusions, Phrase, Phrases, Tag, Tags};
 
-pub async fn load_phrases(
+/// Load a clip's phrases, tags, and availability windows.
+pub async fn load_details(
     clip: &mut Clip,
     connection: &mut SqliteConnection,
 ) -> Result<(), crate::Error> {
     let db_phrases = db::phrases_for_clip(&mut *connection, clip.uuid.clone()).await?;
     clip.phrases = Some(db_phrases_to_api(db_phrases));
+    clip.tags = db::tags_for_clips(&mut *connection, std::slice::from_ref(&clip.uuid))
+        .await?
+        .remove(&clip.uuid)
+        .unwrap_or_default();
+    clip.availability =
+        db::availability_for_clips(&mut *connection, std::slice::from_ref(&clip.uuid))
+            .await?
+            .remove(&clip.uuid)
+            .unwrap_or_default();
+    Ok(())
+}
+
+/// Load the phrases, tags, and availability windows for several clips with a query for each.
+pub async fn load_details_for_clips(
+    clips: &mut [Clip],
+    connection: &mut SqliteConnection,
+) -> Result<(), crate::Error> {
+    let uuids = clips
+        .iter()
+        .map(|clip| clip.uuid.clone())
+        .collect::<Vec<_>>();
+    let mut phrases_by_clip: HashMap<String, Vec<db::Phrase>> = HashMap::new();
+    for phrase in db::phrases_for_clips(&mut *connection, &uuids).await? {
+        for clip in phrase.clips.iter() {
+            phrases_by_clip
+                .entry(clip.clone())
+                .or_default()
+                .push(phrase.clone());
+        }
+    }
+    let mut tags_by_clip = db::tags_for_clips(&mut *connection, &uuids).await?;
+    let mut availability_by_clip = db::availability_for_clips(&mut *connection, &uuids).await?;
+    for clip in clips.iter_mut() {
+        let db_phrases = phrases_by_clip.remove(&clip.uuid).unwrap_or_default();
+        clip.phrases = Some(db_phrases_to_api(db_phrases));
+        clip.tags = tags_by_clip.remove(&clip.uuid).unwrap_or_default();
+        clip.availability = availability_by_clip.remove(&clip.uuid).unwrap_or_default();
+    }
     Ok(())
 }
 
@@ -18,6 +59,32 @@ impl From<db::Phrase> for Phrase {
         Self {
             uuid: phrase.uuid,
             phrase: phrase.phrase,
+            clips: phrase.clips,
+            similarity_threshold: phrase.similarity_threshold,
+            substring: phrase.substring,
+            kind: phrase.kind.into(),
+            allowed_speakers: phrase.speakers.allowed,
+            denied_speakers: phrase.speakers.denied,
+        }
+    }
+}
+
+impl From<db::Exclusion> for Exclusion {
+    fn from(exclusion: db::Exclusion) -> Self {
+        Self {
+            uuid: exclusion.uuid,
+            clip: exclusion.clip,
+            phrase: exclusion.phrase,
+        }
+    }
+}
+
+impl From<db::Tag> for Tag {
+    fn from(tag: db::Tag) -> Self {
+        Self {
+            uuid: tag.uuid,
+            name: tag.name,
+            clips: tag.clips,
         }
     }
 }
@@ -32,3 +99,17 @@ pub fn db_phrases_to_api(phrases: Vec<db::Phrase>) -> Phrases {
             .collect::<Vec<Phrase>>(),
     }
 }
+
+pub fn db_exclusions_to_api(exclusions: Vec<db::Exclusion>) -> Exclusions {
+    Exclusions {
+        items: exclusions.len() as u64,
+        exclusions: exclusions.into_iter().map(Into::into).collect(),
+    }
+}
+
+pub fn db_tags_to_api(tags: Vec<db::Tag>) -> Tags {
+    Tags {
+        items: tags.len() as u64,
+        tags: tags.into_iter().map(Into::into).collect(),
+    }
+}